@@ -0,0 +1,484 @@
+use crate::bigint::BigUint;
+use crate::encoder::Encoder;
+use crate::rng::Rng;
+use crate::solver::Solver;
+use crate::terminator::Terminator;
+use crate::words::{Bits, Word};
+use crate::Status;
+
+/// Approximate model counter in the style of ApproxMC, for instances too large to count exactly.
+/// Random XOR constraints over the sampling set split the projected models into cells of
+/// roughly equal size, and the number of constraints leaving a cell smaller than a threshold
+/// gives an estimate of the count. The median of independent estimates is within a factor
+/// `1 + epsilon` of the projected model count with probability at least `1 - delta`.
+///
+/// Each estimate uses a solver of its own, solving incrementally as hashes are added and cells
+/// enumerated. The hashes are native XOR constraints unless disabled, in which case they are
+/// encoded into clauses through `Bits::xor`.
+pub struct ApproxCounter {
+    num_vars: usize,
+    clauses: Vec<Vec<i32>>,
+    xors: Vec<Vec<i32>>,
+    projection: Option<Vec<i32>>,
+    epsilon: f64,
+    delta: f64,
+    seed: u64,
+    native_xor: bool,
+    terminator: Option<Terminator>,
+    status: Status,
+}
+
+impl ApproxCounter {
+    /// Create a counter with the given tolerance and confidence.
+    pub fn new(epsilon: f64, delta: f64) -> ApproxCounter {
+        assert!(epsilon > 0.0);
+        assert!(delta > 0.0 && delta < 1.0);
+
+        ApproxCounter {
+            num_vars: 0,
+            clauses: Vec::new(),
+            xors: Vec::new(),
+            projection: None,
+            epsilon,
+            delta,
+            seed: 0,
+            native_xor: true,
+            terminator: None,
+            status: Status::Unknown,
+        }
+    }
+
+    /// The number of variables in use.
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// Seed the random hashes, so that estimates are reproducible.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Encode the hashes into clauses rather than adding them as XOR constraints.
+    pub fn set_native_xor(&mut self, native: bool) {
+        self.native_xor = native;
+    }
+
+    /// Add a clause.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        self.reserve(clause);
+        self.clauses.push(clause.to_vec());
+    }
+
+    /// Add an XOR constraint, given as literals whose exclusive or is true.
+    pub fn add_xor(&mut self, xor: &[i32]) {
+        self.reserve(xor);
+        self.xors.push(xor.to_vec());
+    }
+
+    /// Count the assignments to the given variables only, such as those of a `c ind` line.
+    pub fn set_projection(&mut self, vars: &[i32]) {
+        self.reserve(vars);
+        self.projection = Some(vars.to_vec());
+    }
+
+    /// Add the clauses and XOR constraints encoded so far, and count the values of the given
+    /// words only.
+    pub fn project_words(&mut self, encoder: &mut Encoder, words: &[&Word]) {
+        let vars: Vec<i32> = words
+            .iter()
+            .flat_map(|w| encoder.word(w))
+            .map(|l| l.abs())
+            .collect();
+
+        for clause in encoder.take_clauses() {
+            self.add_clause(&clause);
+        }
+
+        for xor in encoder.take_xors() {
+            self.add_xor(&xor);
+        }
+
+        self.num_vars = self.num_vars.max(encoder.num_vars());
+        self.set_projection(&vars);
+    }
+
+    /// Give up once the terminator stops, its conflict limit applying to each call of the
+    /// solvers.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// An estimate of the number of models, or of projected assignments. A count stopped by the
+    /// terminator is zero.
+    pub fn count(&mut self) -> BigUint {
+        let threshold = self.threshold();
+        let mut rng = Rng::new(self.seed);
+
+        self.status = Status::Unknown;
+
+        // counts below the threshold are exact
+        let mut cells = self.cells();
+        let size = cells.enumerate(0, threshold).len();
+
+        if cells.stopped() {
+            return BigUint::from(0);
+        }
+
+        if size < threshold {
+            self.status = if size == 0 {
+                Status::Unsat
+            } else {
+                Status::Sat
+            };
+
+            return BigUint::from(size as u64);
+        }
+
+        let mut estimates = Vec::new();
+
+        for _ in 0..self.iterations() {
+            let mut cells = self.cells();
+
+            // binary search for the fewest hashes leaving a cell below the threshold, which
+            // holds for none of them, and is taken to hold for all
+            let (mut lo, mut hi) = (0, cells.len());
+            let mut size = None;
+
+            while hi - lo > 1 {
+                let mid = (lo + hi) / 2;
+                let models = cells.enumerate_hashed(mid, threshold, &mut rng).len();
+
+                if models < threshold {
+                    hi = mid;
+                    size = Some(models);
+                } else {
+                    lo = mid;
+                }
+            }
+
+            let size = match size {
+                Some(size) => size,
+                None => cells.enumerate_hashed(hi, threshold, &mut rng).len(),
+            };
+
+            if cells.stopped() {
+                return BigUint::from(0);
+            }
+
+            estimates.push(&BigUint::from(size as u64) * &BigUint::pow2(hi));
+        }
+
+        estimates.sort_unstable();
+        self.status = Status::Sat;
+        estimates.swap_remove(estimates.len() / 2)
+    }
+
+    /// The outcome of the last count: `Status::Unknown` if stopped by the terminator, and
+    /// otherwise whether the count is positive.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The size below which cells are counted by enumeration.
+    fn threshold(&self) -> usize {
+        let e = self.epsilon;
+
+        (1.0 + 9.84 * (1.0 + e / (1.0 + e)) * (1.0 + 1.0 / e).powi(2)).ceil() as usize
+    }
+
+    /// The number of independent estimates needed for the confidence.
+    fn iterations(&self) -> usize {
+        (17.0 * (3.0 / self.delta).log2()).ceil() as usize
+    }
+
+    fn reserve(&mut self, clause: &[i32]) {
+        if let Some(max) = clause.iter().map(|l| l.unsigned_abs() as usize).max() {
+            self.num_vars = self.num_vars.max(max);
+        }
+    }
+
+    /// A solver of its own for the clauses, to be hashed over the sampling set.
+    pub(crate) fn cells(&self) -> Cells {
+        let vars: Vec<i32> = match &self.projection {
+            None => (1..=self.num_vars as i32).collect(),
+            Some(vars) => vars.clone(),
+        };
+
+        Cells::new(
+            self.num_vars,
+            &self.clauses,
+            &self.xors,
+            &vars,
+            self.native_xor,
+            self.terminator.as_ref(),
+        )
+    }
+}
+
+/// The cells of a family of random hashes over a sampling set. Each hash is a random XOR
+/// constraint over the sampling variables enabled by assuming a literal, and the cell of the
+/// first `m` hashes holds the models satisfying all of them, distinct over the sampling set.
+pub(crate) struct Cells {
+    solver: Solver,
+    vars: Vec<i32>,
+    hashes: Vec<i32>,
+    encoder: Option<(Encoder, Bits, Vec<u32>)>,
+    num_vars: usize,
+    stopped: bool,
+}
+
+impl Cells {
+    /// A solver for the clauses and XOR constraints over `num_vars` variables, hashed over the
+    /// sampling variables `vars` with native XOR constraints or else with clauses encoded
+    /// through `Bits::xor`. The solver is stopped by the terminator, if any.
+    pub(crate) fn new(
+        num_vars: usize,
+        clauses: &[Vec<i32>],
+        xors: &[Vec<i32>],
+        vars: &[i32],
+        native_xor: bool,
+        terminator: Option<&Terminator>,
+    ) -> Cells {
+        let mut solver = Solver::new();
+
+        if let Some(terminator) = terminator {
+            solver.set_terminator(terminator.clone());
+        }
+
+        for clause in clauses {
+            solver.add_clause(clause);
+        }
+
+        for xor in xors {
+            solver.add_xor(xor);
+        }
+
+        let mut vars = vars.to_vec();
+
+        vars.sort_unstable();
+        vars.dedup();
+
+        let mut encoder = None;
+
+        if !native_xor {
+            let bits = Bits::new();
+            let mut inner = Encoder::new(&bits, num_vars);
+            let mut ids = Vec::new();
+
+            // an expression equivalent to each sampling variable
+            for v in &vars {
+                let id = bits.var();
+                let l = inner.lit(id);
+
+                solver.add_clause(&[-l, *v]);
+                solver.add_clause(&[l, -v]);
+                ids.push(id);
+            }
+
+            encoder = Some((inner, bits, ids));
+        }
+
+        Cells {
+            solver,
+            vars,
+            hashes: Vec::new(),
+            encoder,
+            num_vars,
+            stopped: false,
+        }
+    }
+
+    /// The number of sampling variables.
+    pub(crate) fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    /// The sampling variables.
+    pub(crate) fn vars(&self) -> &[i32] {
+        &self.vars
+    }
+
+    /// Whether the terminator stopped an enumeration, leaving it incomplete.
+    pub(crate) fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Up to `limit` models in the cell of the first `m` hashes, drawing hashes as needed.
+    pub(crate) fn enumerate_hashed(
+        &mut self,
+        m: usize,
+        limit: usize,
+        rng: &mut Rng,
+    ) -> Vec<Vec<i32>> {
+        while self.hashes.len() < m {
+            self.hash(rng);
+        }
+
+        self.enumerate(m, limit)
+    }
+
+    /// Up to `limit` models in the cell of the first `m` hashes, distinct over the sampling
+    /// variables, each with one literal per variable as returned by `Solver::model`.
+    pub(crate) fn enumerate(&mut self, m: usize, limit: usize) -> Vec<Vec<i32>> {
+        // the blocking clauses only last for this call
+        let activation = self.fresh();
+
+        self.solver.push(activation);
+
+        let mut models = Vec::new();
+
+        while models.len() < limit {
+            match self.solver.solve_with_assumptions(&self.hashes[..m]) {
+                Status::Sat => {}
+                Status::Unsat => break,
+
+                Status::Unknown => {
+                    self.stopped = true;
+                    break;
+                }
+            }
+
+            let blocking: Vec<i32> = self
+                .vars
+                .iter()
+                .map(|v| {
+                    if self.solver.value(*v) == Some(true) {
+                        -v
+                    } else {
+                        *v
+                    }
+                })
+                .collect();
+
+            self.solver.add_clause(&blocking);
+            models.push(self.solver.model());
+        }
+
+        self.solver.pop();
+
+        models
+    }
+
+    /// Draw the hashes anew as they are needed, leaving the earlier ones disabled.
+    pub(crate) fn rehash(&mut self) {
+        self.hashes.clear();
+    }
+
+    /// Draw another hash, including each sampling variable with probability one half.
+    fn hash(&mut self, rng: &mut Rng) {
+        let chosen: Vec<usize> = (0..self.vars.len()).filter(|_| rng.next_bool()).collect();
+        let parity = rng.next_bool();
+
+        let l = match &mut self.encoder {
+            None => {
+                self.num_vars += 1;
+
+                let s = self.num_vars as i32;
+                let mut xor: Vec<i32> = chosen.iter().map(|i| self.vars[*i]).collect();
+
+                // with s false the exclusive or of the chosen variables is true and with s true
+                // it is false, while leaving s free leaves them unconstrained
+                xor.push(s);
+                self.solver.add_xor(&xor);
+
+                if parity {
+                    -s
+                } else {
+                    s
+                }
+            }
+
+            Some((encoder, bits, ids)) => {
+                let mut hash = bits.val(parity);
+
+                for i in chosen {
+                    let next = bits.xor(hash, ids[i]);
+
+                    bits.decr(hash);
+                    hash = next;
+                }
+
+                let l = encoder.lit(hash);
+
+                bits.decr(hash);
+                encoder.add_to(&mut self.solver);
+
+                l
+            }
+        };
+
+        self.hashes.push(l);
+    }
+
+    fn fresh(&mut self) -> i32 {
+        match &mut self.encoder {
+            Some((encoder, ..)) => encoder.fresh(),
+
+            None => {
+                self.num_vars += 1;
+                self.num_vars as i32
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::count::Counter;
+    use crate::testing::random_3sat;
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(3);
+
+        for round in 0..2 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 14, 12);
+
+            let mut counter = Counter::new(14);
+            let mut approx = ApproxCounter::new(0.8, 0.5);
+
+            approx.set_seed(round);
+            approx.set_native_xor(round % 2 == 0);
+
+            for clause in &clauses {
+                counter.add_clause(clause);
+                approx.add_clause(clause);
+            }
+
+            let exact = counter.count().to_f64();
+            let estimate = approx.count().to_f64();
+
+            assert!((exact / 1.8..=exact * 1.8).contains(&estimate));
+        }
+    }
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 12);
+        let b = Word::var(&bits, 12);
+        let bound = Word::from_u64(&bits, 12, 1000);
+        let less = (&a + &b).less_than(&bound);
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut approx = ApproxCounter::new(0.8, 0.5);
+
+        approx.add_clause(&[encoder.lit(less.ids()[0])]);
+        approx.project_words(&mut encoder, &[&a]);
+
+        // every a has some b with a + b below 1000 modulo 4096
+        let estimate = approx.count().to_f64();
+
+        assert!((4096.0 / 1.8..=4096.0 * 1.8).contains(&estimate));
+
+        // small counts are exact
+        let small = a.less_than(&Word::from_u64(&bits, 12, 20));
+        let mut approx = ApproxCounter::new(0.8, 0.5);
+
+        approx.add_clause(&[encoder.lit(small.ids()[0])]);
+        approx.project_words(&mut encoder, &[&a]);
+
+        assert_eq!(approx.count(), BigUint::from(20));
+    }
+}
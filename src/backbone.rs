@@ -0,0 +1,177 @@
+use crate::encoder::Encoder;
+use crate::solver::Solver;
+use crate::words::Word;
+use crate::Status;
+
+/// The literals over `vars` that hold in every model of the solver's clauses, in the order of
+/// `vars`, or the status of the solver if the clauses are unsatisfiable or solving was
+/// interrupted.
+///
+/// Candidates start as the literals of a first model, and each model found later rules out the
+/// candidates it falsifies. Candidates are tested in chunks by assuming their complements
+/// together: a model rules out the whole chunk, while a core of a single assumption proves a
+/// backbone literal. Larger cores shrink the chunk, and successful chunks grow it.
+pub fn backbone(solver: &mut Solver, vars: &[i32]) -> Result<Vec<i32>, Status> {
+    match solver.solve() {
+        Status::Sat => {}
+        status => return Err(status),
+    }
+
+    let mut candidates: Vec<i32> = vars
+        .iter()
+        .map(|v| {
+            if solver.value(*v) == Some(true) {
+                *v
+            } else {
+                -v
+            }
+        })
+        .collect();
+
+    let mut fixed = Vec::new();
+    let mut chunk = 1;
+
+    while !candidates.is_empty() {
+        let size = chunk.min(candidates.len());
+        let assumptions: Vec<i32> = candidates[..size].iter().map(|l| -l).collect();
+
+        match solver.solve_with_assumptions(&assumptions) {
+            Status::Sat => {
+                candidates.retain(|l| solver.value(*l) == Some(true));
+                chunk = (2 * chunk).min(64);
+            }
+
+            Status::Unsat => match solver.failed() {
+                [] => return Err(Status::Unsat),
+
+                [l] => {
+                    let l = -l;
+
+                    fixed.push(l);
+                    candidates.retain(|c| *c != l);
+                }
+
+                failed => {
+                    let core: Vec<i32> = failed.iter().map(|l| -l).collect();
+
+                    // test the literals of the core first, half as many at a time
+                    candidates.sort_by_key(|c| !core.contains(c));
+                    chunk = (core.len() / 2).max(1);
+                }
+            },
+
+            Status::Unknown => return Err(Status::Unknown),
+        }
+    }
+
+    Ok(vars
+        .iter()
+        .filter_map(|v| fixed.iter().copied().find(|l| l.abs() == *v))
+        .collect())
+}
+
+/// The value of each bit of each word, least significant first, that is forced by the
+/// solver's clauses, or `None` if they are unsatisfiable or solving was interrupted. The words
+/// are encoded as needed.
+pub fn forced_bits(
+    encoder: &mut Encoder,
+    solver: &mut Solver,
+    words: &[&Word],
+) -> Option<Vec<Vec<Option<bool>>>> {
+    let lits: Vec<Vec<i32>> = words.iter().map(|w| encoder.word(w)).collect();
+
+    encoder.add_to(solver);
+
+    let mut vars: Vec<i32> = lits.iter().flatten().map(|l| l.abs()).collect();
+
+    vars.sort_unstable();
+    vars.dedup();
+
+    let fixed = backbone(solver, &vars).ok()?;
+
+    let value = |l: i32| {
+        fixed
+            .iter()
+            .find(|f| f.abs() == l.abs())
+            .map(|f| (*f > 0) == (l > 0))
+    };
+
+    Some(
+        lits.iter()
+            .map(|word| word.iter().map(|l| value(*l)).collect())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::testing::random_3sat;
+    use crate::words::Bits;
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..40 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 10, 36);
+
+            let models: Vec<Vec<i32>> = (0u32..1 << 10)
+                .map(|bits| {
+                    (1..=10)
+                        .map(|v| if bits >> (v - 1) & 1 == 1 { v } else { -v })
+                        .collect::<Vec<i32>>()
+                })
+                .filter(|m| clauses.iter().all(|c| c.iter().any(|l| m.contains(l))))
+                .collect();
+
+            let mut solver = Solver::new();
+
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+
+            let vars: Vec<i32> = (1..=10).collect();
+
+            match backbone(&mut solver, &vars) {
+                Err(status) => {
+                    assert_eq!(status, Status::Unsat);
+                    assert!(models.is_empty());
+                }
+
+                Ok(fixed) => {
+                    let expected: Vec<i32> = models[0]
+                        .iter()
+                        .copied()
+                        .filter(|l| models.iter().all(|m| m.contains(l)))
+                        .collect();
+
+                    assert_eq!(fixed, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 8);
+        let b = Word::var(&bits, 8);
+        let target = Word::from_u64(&bits, 8, 143);
+        let differs = (&(&a * &b) ^ &target).any();
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut solver = Solver::new();
+
+        solver.add_clause(&[-encoder.lit(differs.ids()[0])]);
+
+        // a * b is odd only if both factors are, and any odd a has a matching b
+        let forced = forced_bits(&mut encoder, &mut solver, &[&a, &b]).unwrap();
+        let expected: Vec<Option<bool>> = (0..8)
+            .map(|i| if i == 0 { Some(true) } else { None })
+            .collect();
+
+        assert_eq!(forced, vec![expected.clone(), expected]);
+    }
+}
@@ -0,0 +1,457 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Sub};
+use std::str::FromStr;
+
+/// Arbitrary precision unsigned integer, as little endian 32 bit limbs without trailing zeros.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> BigUint {
+        BigUint::default()
+    }
+
+    pub fn one() -> BigUint {
+        BigUint::from(1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    pub fn is_one(&self) -> bool {
+        self.limbs == [1]
+    }
+
+    /// Two to the power `k`.
+    pub fn pow2(k: usize) -> BigUint {
+        let mut limbs = vec![0; k / 32 + 1];
+
+        limbs[k / 32] = 1 << (k % 32);
+
+        BigUint { limbs }
+    }
+
+    /// The number of bits, not counting leading zeros.
+    pub fn bits(&self) -> usize {
+        match self.limbs.last() {
+            None => 0,
+            Some(top) => 32 * self.limbs.len() - top.leading_zeros() as usize,
+        }
+    }
+
+    /// The value if it fits in a `u64`.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.limbs[..] {
+            [] => Some(0),
+            [a] => Some(a as u64),
+            [a, b] => Some(a as u64 | (b as u64) << 32),
+            _ => None,
+        }
+    }
+
+    /// The nearest `f64`, or infinity if out of range.
+    pub fn to_f64(&self) -> f64 {
+        self.limbs
+            .iter()
+            .rev()
+            .fold(0.0, |x, limb| x * 4294967296.0 + *limb as f64)
+    }
+
+    /// Divide in place by a small divisor, returning the remainder.
+    pub fn div_rem_u32(&mut self, divisor: u32) -> u32 {
+        assert_ne!(divisor, 0);
+
+        let mut rem = 0u64;
+
+        for limb in self.limbs.iter_mut().rev() {
+            let n = rem << 32 | *limb as u64;
+
+            *limb = (n / divisor as u64) as u32;
+            rem = n % divisor as u64;
+        }
+
+        self.trim();
+
+        rem as u32
+    }
+
+    /// Quotient and remainder, by binary long division.
+    pub fn div_rem(&self, divisor: &BigUint) -> (BigUint, BigUint) {
+        assert!(!divisor.is_zero());
+
+        let mut quotient = BigUint {
+            limbs: vec![0; self.limbs.len()],
+        };
+
+        let mut rem = BigUint::zero();
+
+        for i in (0..self.bits()).rev() {
+            rem = &rem + &rem;
+
+            if self.bit(i) {
+                rem = &rem + &BigUint::one();
+            }
+
+            if rem >= *divisor {
+                rem = &rem - divisor;
+                quotient.limbs[i / 32] |= 1 << (i % 32);
+            }
+        }
+
+        quotient.trim();
+
+        (quotient, rem)
+    }
+
+    /// Greatest common divisor.
+    pub fn gcd(&self, other: &BigUint) -> BigUint {
+        let mut a = self.clone();
+        let mut b = other.clone();
+
+        while !b.is_zero() {
+            let (_, r) = a.div_rem(&b);
+
+            a = b;
+            b = r;
+        }
+
+        a
+    }
+
+    fn bit(&self, i: usize) -> bool {
+        self.limbs[i / 32] >> (i % 32) & 1 == 1
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+}
+
+impl From<u64> for BigUint {
+    fn from(n: u64) -> BigUint {
+        let mut n = BigUint {
+            limbs: vec![n as u32, (n >> 32) as u32],
+        };
+
+        n.trim();
+        n
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &BigUint) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &BigUint) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Add<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, rhs: &BigUint) -> BigUint {
+        let len = self.limbs.len().max(rhs.limbs.len());
+        let mut limbs = Vec::with_capacity(len + 1);
+        let mut carry = 0u64;
+
+        for i in 0..len {
+            let a = *self.limbs.get(i).unwrap_or(&0) as u64;
+            let b = *rhs.limbs.get(i).unwrap_or(&0) as u64;
+            let s = a + b + carry;
+
+            limbs.push(s as u32);
+            carry = s >> 32;
+        }
+
+        limbs.push(carry as u32);
+
+        let mut n = BigUint { limbs };
+
+        n.trim();
+        n
+    }
+}
+
+impl Sub<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    /// Panics if `rhs` is greater.
+    fn sub(self, rhs: &BigUint) -> BigUint {
+        assert!(*self >= *rhs);
+
+        let mut limbs = Vec::with_capacity(self.limbs.len());
+        let mut borrow = 0i64;
+
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut d = *a as i64 - *rhs.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+
+            borrow = (d < 0) as i64;
+            d += borrow << 32;
+            limbs.push(d as u32);
+        }
+
+        let mut n = BigUint { limbs };
+
+        n.trim();
+        n
+    }
+}
+
+impl Mul<&BigUint> for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, rhs: &BigUint) -> BigUint {
+        let mut limbs = vec![0u32; self.limbs.len() + rhs.limbs.len()];
+
+        for (i, a) in self.limbs.iter().enumerate() {
+            let mut carry = 0u64;
+
+            for (j, b) in rhs.limbs.iter().enumerate() {
+                let t = *a as u64 * *b as u64 + limbs[i + j] as u64 + carry;
+
+                limbs[i + j] = t as u32;
+                carry = t >> 32;
+            }
+
+            limbs[i + rhs.limbs.len()] = carry as u32;
+        }
+
+        let mut n = BigUint { limbs };
+
+        n.trim();
+        n
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+
+        let mut n = self.clone();
+        let mut chunks = Vec::new();
+
+        while !n.is_zero() {
+            chunks.push(n.div_rem_u32(1_000_000_000));
+        }
+
+        write!(f, "{}", chunks.pop().unwrap())?;
+
+        for chunk in chunks.iter().rev() {
+            write!(f, "{:09}", chunk)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for BigUint {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<BigUint, ()> {
+        if s.is_empty() {
+            return Err(());
+        }
+
+        let ten = BigUint::from(10);
+
+        s.chars().try_fold(BigUint::zero(), |n, c| {
+            let digit = c.to_digit(10).ok_or(())?;
+
+            Ok(&(&n * &ten) + &BigUint::from(digit as u64))
+        })
+    }
+}
+
+/// Exact non-negative rational number, kept in lowest terms.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: BigUint,
+    den: BigUint,
+}
+
+impl Rational {
+    /// The fraction `num / den` in lowest terms.
+    pub fn new(num: BigUint, den: BigUint) -> Rational {
+        assert!(!den.is_zero());
+
+        if den.is_one() {
+            return Rational { num, den };
+        }
+
+        let gcd = num.gcd(&den);
+
+        Rational {
+            num: num.div_rem(&gcd).0,
+            den: den.div_rem(&gcd).0,
+        }
+    }
+
+    pub fn zero() -> Rational {
+        Rational::from(BigUint::zero())
+    }
+
+    pub fn one() -> Rational {
+        Rational::from(BigUint::one())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    pub fn numerator(&self) -> &BigUint {
+        &self.num
+    }
+
+    pub fn denominator(&self) -> &BigUint {
+        &self.den
+    }
+
+    /// The value as an integer, if it is one.
+    pub fn to_integer(&self) -> Option<BigUint> {
+        if self.den.is_one() {
+            Some(self.num.clone())
+        } else {
+            None
+        }
+    }
+
+    /// The nearest `f64`, losing precision for large terms.
+    pub fn to_f64(&self) -> f64 {
+        // scale both terms down to the range of f64 together
+        let shift = self.num.bits().max(self.den.bits()).saturating_sub(1000);
+        let scale = BigUint::pow2(shift);
+
+        self.num.div_rem(&scale).0.to_f64() / self.den.div_rem(&scale).0.to_f64()
+    }
+}
+
+impl From<BigUint> for Rational {
+    fn from(n: BigUint) -> Rational {
+        Rational {
+            num: n,
+            den: BigUint::one(),
+        }
+    }
+}
+
+impl Add<&Rational> for &Rational {
+    type Output = Rational;
+
+    fn add(self, rhs: &Rational) -> Rational {
+        if self.den == rhs.den {
+            return Rational::new(&self.num + &rhs.num, self.den.clone());
+        }
+
+        Rational::new(
+            &(&self.num * &rhs.den) + &(&rhs.num * &self.den),
+            &self.den * &rhs.den,
+        )
+    }
+}
+
+impl Mul<&Rational> for &Rational {
+    type Output = Rational;
+
+    fn mul(self, rhs: &Rational) -> Rational {
+        Rational::new(&self.num * &rhs.num, &self.den * &rhs.den)
+    }
+}
+
+/// Parses integers, fractions such as `3/4` and decimals such as `0.25`.
+impl FromStr for Rational {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Rational, ()> {
+        if let Some((num, den)) = s.split_once('/') {
+            let den: BigUint = den.parse()?;
+
+            if den.is_zero() {
+                return Err(());
+            }
+
+            return Ok(Rational::new(num.parse()?, den));
+        }
+
+        let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let digits = format!("{}{}", whole, fraction);
+        let den = (0..fraction.len()).fold(BigUint::one(), |d, _| &d * &BigUint::from(10));
+
+        Ok(Rational::new(digits.parse()?, den))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.den.is_one() {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simple_01() {
+        let a = BigUint::from(u64::MAX);
+        let b = &a + &BigUint::one();
+
+        assert_eq!(b, BigUint::pow2(64));
+        assert_eq!(b.bits(), 65);
+        assert_eq!(b.to_u64(), None);
+        assert_eq!(
+            (&a * &a).to_string(),
+            "340282366920938463426481119284349108225"
+        );
+        assert_eq!(
+            BigUint::pow2(100).to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(BigUint::zero().to_string(), "0");
+        assert!(a < b);
+    }
+
+    #[test]
+    fn div_01() {
+        let a: BigUint = "340282366920938463426481119284349108225".parse().unwrap();
+        let b = BigUint::from(u64::MAX);
+        let (q, r) = a.div_rem(&BigUint::from(1_000_003));
+
+        assert_eq!(a.div_rem(&b), (b.clone(), BigUint::zero()));
+        assert_eq!(&(&q * &BigUint::from(1_000_003)) + &r, a);
+        assert_eq!(a.gcd(&BigUint::from(15 * 17)), BigUint::from(15 * 17));
+        assert_eq!(&a - &a, BigUint::zero());
+    }
+
+    #[test]
+    fn rational_01() {
+        let a: Rational = "0.25".parse().unwrap();
+        let b: Rational = "6/8".parse().unwrap();
+
+        assert_eq!(a.to_string(), "1/4");
+        assert_eq!(b.to_string(), "3/4");
+        assert_eq!(&a + &b, Rational::one());
+        assert_eq!((&a * &b).to_string(), "3/16");
+        assert_eq!((&a * &b).to_f64(), 0.1875);
+        assert!("1/0".parse::<Rational>().is_err());
+        assert!("x".parse::<Rational>().is_err());
+    }
+}
@@ -1,109 +1,1941 @@
 #![allow(unused)]
 
+use rsat::approx::ApproxCounter;
+use rsat::backbone::backbone;
+use rsat::bigint::Rational;
+use rsat::count::Counter;
+use rsat::cube::{Conquer, Cuber};
 use rsat::dimacs::*;
-use std::collections::{HashMap, HashSet};
+use rsat::dp::DavisPutnam;
+use rsat::drat::Drat;
+use rsat::json::{Object, ToJson};
+use rsat::maxsat::{Algorithm, MaxSat};
+use rsat::mus::Mus;
+use rsat::opb::Opb;
+use rsat::pb::{self, Encoding};
+use rsat::portfolio::Portfolio;
+use rsat::proof::*;
+use rsat::qbf::Qbf;
+use rsat::sample::Sampler;
+use rsat::sls::LocalSearch;
+use rsat::solution::*;
+use rsat::solver::Solver;
+use rsat::stats::{Observer, Stats};
+use rsat::terminator::Terminator;
+use rsat::Status;
 use std::env;
 use std::fs::File;
 use std::io::prelude::*;
-use std::io::BufReader;
+use std::io::{self, BufReader, BufWriter};
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use lzma::LzmaReader;
 
-fn main() -> std::io::Result<()> {
+const USAGE: &str = "USAGE: rsat <COMMAND> [OPTIONS] FILE
+       rsat verify [OPTIONS] FILE MODEL
+       rsat check [OPTIONS] FILE PROOF
+
+Commands:
+    solve       solve a CNF or XOR-CNF instance and print the result in SAT competition format
+    maxsat      find an optimal model of a WCNF instance in MaxSAT evaluation format
+    qbf         decide a QDIMACS formula and print the result in QDIMACS output format
+    pb          solve or optimize an OPB instance and print the result in PB competition format
+    mus         write a minimal unsatisfiable subset of a CNF instance, with its clause indices
+    backbone    print the literals of a CNF instance that hold in every model
+    count       count the models of a CNF instance, projected or weighted as its comments declare
+    sample      print near-uniform random models of a CNF instance, projected onto its c ind line
+    cube        split a CNF instance into cubes by lookahead and write them in iCNF format
+    conquer     solve the cubes of an iCNF instance and print the result in SAT competition format
+    simplify    eliminate variables and write the simplified CNF
+    stats       print statistics about a CNF instance
+    convert     convert between CNF, QDIMACS, iCNF and WCNF, plain or xz compressed
+    verify      check a model in SAT competition format against a CNF instance
+    check       check a DRAT or LRAT refutation of a CNF instance
+
+Options:
+    -o, --output FILE        write output to FILE instead of standard output
+    -f, --format NAME        format to convert to, cnf, qdimacs, icnf, wcnf or wcnf-legacy
+                             (default that of the input)
+    -t, --time-limit SECS    give up after SECS seconds
+        --conflicts N        give up after N conflicts when solving or counting
+        --memory MB          give up once the process takes more than MB megabytes
+    -s, --seed N             seed for the randomized heuristics
+    -a, --algorithm NAME     MaxSAT search strategy, oll or linear (default oll)
+    -e, --encoding NAME      pseudo-Boolean encoding, bdd, sorter or totalizer (default bdd)
+    -r, --rounds N           maximum number of variable eliminations (default 100)
+        --epsilon E          count approximately, within a factor 1 + E of the exact count
+        --delta D            probability of the approximate count being off (default 0.2)
+    -n, --samples N          number of models to sample (default 1)
+    -l, --local-search       try local search before solving, and start from its best assignment
+    -j, --threads N          solve with a portfolio of N solvers, or conquer cubes with N threads
+    -d, --depth N            number of decisions of each cube (default 10)
+    -p, --proof FILE         write a DRAT proof of solving or simplification to FILE, or the
+                             LRAT proof produced by checking a DRAT proof
+    -b, --binary-proof       write the proof in binary DRAT format
+        --lrat               read the proof to check in LRAT format
+        --core FILE          write the unsatisfiable core found by the proof check to FILE
+    -v, --verbose            print progress as comment lines, repeat for more detail
+        --json               print a JSON report of the results instead of the usual output
+    -h, --help               print this message
+
+Input files may be xz compressed. Output files ending in .xz are compressed.";
+
+/// Magic bytes at the start of an xz stream.
+const XZ_MAGIC: [u8; 6] = [0xfd, b'7', b'z', b'X', b'Z', 0x00];
+
+/// File formats the convert command reads and writes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Cnf,
+    Qdimacs,
+    Icnf,
+    Wcnf,
+    WcnfLegacy,
+}
+
+struct Options {
+    command: String,
+    inputs: Vec<String>,
+    output: Option<String>,
+    format: Option<Format>,
+    time_limit: Option<Duration>,
+    max_conflicts: Option<u64>,
+    max_memory: Option<usize>,
+    seed: Option<u64>,
+    algorithm: Algorithm,
+    encoding: Encoding,
+    rounds: usize,
+    epsilon: Option<f64>,
+    delta: f64,
+    samples: usize,
+    local_search: bool,
+    threads: usize,
+    depth: usize,
+    proof: Option<String>,
+    binary_proof: bool,
+    lrat: bool,
+    core: Option<String>,
+    verbosity: u32,
+    json: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+
+    let command = args.next().ok_or("missing command")?.clone();
+
+    if ![
+        "solve", "maxsat", "qbf", "pb", "mus", "backbone", "count", "sample", "cube", "conquer",
+        "simplify", "stats", "convert", "verify", "check",
+    ]
+    .contains(&command.as_str())
+    {
+        return Err(format!("unknown command '{}'", command));
+    }
+
+    let mut options = Options {
+        command,
+        inputs: vec![],
+        output: None,
+        format: None,
+        time_limit: None,
+        max_conflicts: None,
+        max_memory: None,
+        seed: None,
+        algorithm: Algorithm::Oll,
+        encoding: Encoding::Bdd,
+        rounds: 100,
+        epsilon: None,
+        delta: 0.2,
+        samples: 1,
+        local_search: false,
+        threads: 1,
+        depth: 10,
+        proof: None,
+        binary_proof: false,
+        lrat: false,
+        core: None,
+        verbosity: 0,
+        json: false,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or(format!("missing value for {}", name))
+        };
+
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(value(arg)?),
+
+            "-f" | "--format" => {
+                options.format = Some(match value(arg)?.as_str() {
+                    "cnf" => Format::Cnf,
+                    "qdimacs" => Format::Qdimacs,
+                    "icnf" => Format::Icnf,
+                    "wcnf" => Format::Wcnf,
+                    "wcnf-legacy" => Format::WcnfLegacy,
+                    other => return Err(format!("unknown format '{}'", other)),
+                });
+            }
+
+            "-t" | "--time-limit" => {
+                let secs: f64 = value(arg)?
+                    .parse()
+                    .map_err(|_| "time limit must be a number of seconds")?;
+
+                options.time_limit = Some(Duration::from_secs_f64(secs));
+            }
+
+            "--conflicts" => {
+                let conflicts = value(arg)?
+                    .parse()
+                    .map_err(|_| "conflicts must be an integer")?;
+
+                options.max_conflicts = Some(conflicts);
+            }
+
+            "--memory" => {
+                let megabytes: usize = value(arg)?
+                    .parse()
+                    .map_err(|_| "memory must be a number of megabytes")?;
+
+                options.max_memory = Some(megabytes << 20);
+            }
+
+            "-s" | "--seed" => {
+                options.seed = Some(value(arg)?.parse().map_err(|_| "seed must be an integer")?);
+            }
+
+            "-a" | "--algorithm" => {
+                options.algorithm = match value(arg)?.as_str() {
+                    "oll" => Algorithm::Oll,
+                    "linear" => Algorithm::Linear,
+                    other => return Err(format!("unknown algorithm '{}'", other)),
+                };
+            }
+
+            "-e" | "--encoding" => {
+                options.encoding = match value(arg)?.as_str() {
+                    "bdd" => Encoding::Bdd,
+                    "sorter" => Encoding::Sorter,
+                    "totalizer" => Encoding::Totalizer,
+                    other => return Err(format!("unknown encoding '{}'", other)),
+                };
+            }
+
+            "-r" | "--rounds" => {
+                options.rounds = value(arg)?
+                    .parse()
+                    .map_err(|_| "rounds must be an integer")?;
+            }
+
+            "--epsilon" => {
+                let epsilon: f64 = value(arg)?
+                    .parse()
+                    .map_err(|_| "epsilon must be a number")?;
+
+                if epsilon <= 0.0 {
+                    return Err("epsilon must be positive".to_string());
+                }
+
+                options.epsilon = Some(epsilon);
+            }
+
+            "--delta" => {
+                options.delta = value(arg)?.parse().map_err(|_| "delta must be a number")?;
+
+                if !(options.delta > 0.0 && options.delta < 1.0) {
+                    return Err("delta must be between 0 and 1".to_string());
+                }
+            }
+
+            "-n" | "--samples" => {
+                options.samples = value(arg)?
+                    .parse()
+                    .map_err(|_| "samples must be an integer")?;
+            }
+
+            "-l" | "--local-search" => options.local_search = true,
+
+            "-j" | "--threads" => {
+                options.threads = value(arg)?
+                    .parse()
+                    .map_err(|_| "threads must be an integer")?;
+
+                if options.threads == 0 {
+                    return Err("threads must be positive".to_string());
+                }
+            }
+
+            "-d" | "--depth" => {
+                options.depth = value(arg)?
+                    .parse()
+                    .map_err(|_| "depth must be an integer")?;
+            }
+
+            "-p" | "--proof" => options.proof = Some(value(arg)?),
+
+            "-b" | "--binary-proof" => options.binary_proof = true,
+
+            "--lrat" => options.lrat = true,
+
+            "--core" => options.core = Some(value(arg)?),
+
+            "--verbose" => options.verbosity += 1,
+
+            "--json" => options.json = true,
+
+            _ if arg
+                .strip_prefix('-')
+                .is_some_and(|flags| !flags.is_empty() && flags.chars().all(|c| c == 'v')) =>
+            {
+                options.verbosity += arg.len() as u32 - 1;
+            }
+
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option '{}'", arg));
+            }
+
+            _ => options.inputs.push(arg.clone()),
+        }
+    }
+
+    let expected = match options.command.as_str() {
+        "verify" | "check" => 2,
+        _ => 1,
+    };
+
+    if options.inputs.len() < expected {
+        return Err("missing input file".to_string());
+    }
+
+    if options.inputs.len() > expected {
+        return Err("too many input files".to_string());
+    }
+
+    // comment lines would break the report
+    if options.json && options.verbosity > 0 {
+        return Err("--json cannot be combined with --verbose".to_string());
+    }
+
+    if options.json && options.command == "convert" {
+        return Err("--json is not supported by convert".to_string());
+    }
+
+    Ok(options)
+}
+
+fn main() -> io::Result<()> {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 2 {
-        println!("USAGE: {} DIMACS_FILE", args[0]);
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let options = match parse_args(&args[1..]) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            process::exit(1);
+        }
+    };
+
+    let code = match options.command.as_str() {
+        "solve" => solve(&options)?,
+        "maxsat" => maxsat(&options)?,
+        "qbf" => qbf(&options)?,
+        "pb" => pseudo_boolean(&options)?,
+        "mus" => mus(&options)?,
+        "backbone" => print_backbone(&options)?,
+        "count" if options.epsilon.is_some() => approx_count(&options)?,
+        "count" => count(&options)?,
+        "sample" => sample(&options)?,
+        "cube" => cube(&options)?,
+        "conquer" => conquer(&options)?,
+        "simplify" => simplify(&options)?,
+        "stats" => stats(&options)?,
+        "convert" => convert(&options)?,
+        "verify" => verify(&options)?,
+        "check" => check(&options)?,
+        _ => unreachable!(),
+    };
+
+    process::exit(code)
+}
+
+/// Open the input file, or standard input for `-`, transparently decompressing xz streams.
+fn open_input(path: &str) -> io::Result<Box<dyn BufRead>> {
+    let io: Box<dyn Read> = if path == "-" {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path)?)
+    };
+
+    let mut io = BufReader::new(io);
+
+    if io.fill_buf()?.starts_with(&XZ_MAGIC) {
+        let lzma = LzmaReader::new_decompressor(io)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        Ok(Box::new(BufReader::new(lzma)))
+    } else {
+        Ok(Box::new(io))
+    }
+}
+
+/// Read the clauses of the input file along with the number of variables.
+fn read_cnf(options: &Options) -> io::Result<(usize, Vec<Vec<i32>>)> {
+    let (num_vars, clauses, xors) = read_xcnf(options)?;
+
+    no_xors(&xors)?;
+    Ok((num_vars, clauses))
+}
+
+fn no_xors(xors: &[Vec<i32>]) -> io::Result<()> {
+    if xors.is_empty() {
         return Ok(());
     }
 
-    let file = File::open(&args[1])?;
-    let lzma = lzma::LzmaReader::new_decompressor(file).unwrap();
-    let io = BufReader::new(lzma);
-    let dimacs = Dimacs::new(io);
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "XOR constraints are not supported by this command",
+    ))
+}
+
+/// The number of variables, clauses and XOR constraints of a formula.
+type XorFormula = (usize, Vec<Vec<i32>>, Vec<Vec<i32>>);
+
+/// Read a DIMACS formula along with its XOR constraints.
+fn read_xcnf(options: &Options) -> io::Result<XorFormula> {
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+    let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+    let xors = dimacs.xors().to_vec();
+
+    let declared = dimacs.header().map(|h| h.0).unwrap_or(0);
+    let used = clauses
+        .iter()
+        .chain(xors.iter())
+        .flatten()
+        .map(|l| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    Ok((declared.max(used), clauses, xors))
+}
+
+/// The variables to count or sample: those of the `c ind` lines, or else every variable declared
+/// or used, as variables declared but unused double the count as well.
+fn projection<R: BufRead>(dimacs: &Dimacs<R>, clauses: &[Vec<i32>]) -> Vec<i32> {
+    if let Some(vars) = dimacs.projection() {
+        return vars.to_vec();
+    }
+
+    let declared = dimacs.header().map(|h| h.0).unwrap_or(0);
+    let used = clauses
+        .iter()
+        .chain(dimacs.xors().iter())
+        .flatten()
+        .map(|l| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    (1..=declared.max(used) as i32).collect()
+}
+
+/// Write the output to the output file, compressing it if the file name ends in `.xz`, or to
+/// standard output.
+fn write_output(options: &Options, data: &[u8]) -> io::Result<()> {
+    match &options.output {
+        None => io::stdout().write_all(data),
+
+        Some(path) if path.ends_with(".xz") => {
+            let data = lzma::compress(data, 6).map_err(io::Error::other)?;
+
+            File::create(path)?.write_all(&data)
+        }
+
+        Some(path) => File::create(path)?.write_all(data),
+    }
+}
+
+/// Create the DRAT proof requested on the command line, if any.
+fn open_proof(options: &Options) -> io::Result<Option<Drat>> {
+    match &options.proof {
+        None => Ok(None),
+        Some(path) => {
+            let io = BufWriter::new(File::create(path)?);
+
+            Ok(Some(Drat::new(io, options.binary_proof)))
+        }
+    }
+}
+
+/// A JSON report of the results of the command, starting with its name.
+fn report(options: &Options) -> Object {
+    let mut report = Object::new();
+
+    report.field("command", &options.command);
+    report
+}
+
+/// Write the report to the output, on a single line.
+fn write_report(options: &Options, report: &Object) -> io::Result<()> {
+    write_output(options, format!("{}\n", report).as_bytes())
+}
+
+/// A terminator with the limits of the options, the time limit counting from `start`.
+fn terminator(options: &Options, start: Instant) -> Terminator {
+    let mut terminator = Terminator::new();
 
-    let mut clauses: Vec<Vec<i32>> = dimacs.collect();
+    if let Some(limit) = options.time_limit {
+        terminator.set_deadline(start + limit);
+    }
 
-    for _ in 0..100 {
-        let pivot = *clauses.first().unwrap().first().unwrap();
-        let before = clauses.len();
+    if let Some(conflicts) = options.max_conflicts {
+        terminator.set_max_conflicts(conflicts);
+    }
 
-        conflicts(&mut clauses, pivot);
+    if let Some(memory) = options.max_memory {
+        terminator.set_max_memory(memory);
+    }
 
-        let after = clauses.len();
+    terminator
+}
 
-        let before = before as f32;
-        let after = after as f32;
+/// Prints each elimination as a comment line.
+struct Progress;
 
+impl Observer for Progress {
+    fn eliminated(&mut self, pivot: i32, before: usize, after: usize) {
         println!(
-            "Before/After: {} {} {:0.02}",
+            "c eliminated {}: {} -> {} clauses",
+            pivot.abs(),
             before,
-            after,
-            100.0 * (after - before) / before
+            after
+        );
+    }
+}
+
+/// Records each elimination as a JSON object.
+struct Record(Arc<Mutex<Vec<Object>>>);
+
+impl Observer for Record {
+    fn eliminated(&mut self, pivot: i32, before: usize, after: usize) {
+        let mut round = Object::new();
+
+        round
+            .field("pivot", &pivot.abs())
+            .field("before", &before)
+            .field("after", &after);
+
+        self.0.lock().unwrap().push(round);
+    }
+}
+
+/// Print the statistics as comment lines.
+fn print_stats(stats: &Stats) {
+    println!(
+        "c {} conflicts, {} decisions, {} propagations, {} restarts",
+        stats.conflicts, stats.decisions, stats.propagations, stats.restarts
+    );
+
+    println!(
+        "c {} learnt clauses, {} units, {} eliminations",
+        stats.learnts, stats.units, stats.eliminations
+    );
+
+    println!(
+        "c {:.3}s eliminating, {:.3}s searching",
+        stats.elimination_time.as_secs_f64(),
+        stats.search_time.as_secs_f64()
+    );
+}
+
+/// Eliminate variables for as long as doing so does not grow the clause set, up to the
+/// configured number of rounds. Returns the status if reached, along with the effect of each
+/// round when a JSON report is requested.
+fn eliminate(dp: &mut DavisPutnam, options: &Options) -> (Option<Status>, Vec<Object>) {
+    let rounds = Arc::new(Mutex::new(Vec::new()));
+
+    if options.json {
+        dp.set_observer(Some(Box::new(Record(rounds.clone()))));
+    } else if options.verbosity >= 2 {
+        dp.set_observer(Some(Box::new(Progress)));
+    }
+
+    for _ in 0..options.rounds {
+        if dp.status().is_some() {
+            break;
+        }
+
+        let pivot = match dp.pivot() {
+            Some(pivot) if dp.growth(pivot) <= 0 => pivot,
+            _ => break,
+        };
+
+        dp.eliminate(pivot);
+    }
+
+    let rounds = rounds.lock().unwrap().drain(..).collect();
+
+    (dp.status(), rounds)
+}
+
+/// The effect of variable elimination, as a JSON object.
+fn preprocessing(dp: &DavisPutnam, rounds: &[Object]) -> Object {
+    let mut preprocessing = Object::new();
+
+    preprocessing
+        .field("eliminated", &dp.eliminated())
+        .field("clauses", &dp.clauses().len())
+        .field("rounds", rounds);
+
+    preprocessing
+}
+
+/// Run local search on the clauses left after elimination.
+fn local_search(dp: &DavisPutnam, options: &Options) -> (Status, LocalSearch) {
+    let mut search = LocalSearch::new(dp.clauses());
+
+    if let Some(seed) = options.seed {
+        search.set_seed(seed);
+    }
+
+    let status = search.solve();
+
+    if options.verbosity >= 1 {
+        println!(
+            "c local search {} after {} flips",
+            match status {
+                Status::Sat => "found a model",
+                _ => "gave up",
+            },
+            search.flips()
         );
     }
 
-    Ok(())
+    (status, search)
 }
 
-fn conflicts(clauses: &mut Vec<Vec<i32>>, pivot: i32) {
-    let mut conflicts = Vec::new();
+fn solve(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let (num_vars, clauses, xors) = read_xcnf(options)?;
+    let num_clauses = clauses.len();
 
-    let mut pos: Vec<usize> = clauses
-        .iter()
-        .enumerate()
-        .filter(|(_, clause)| clause.contains(&pivot))
-        .map(|e| e.0)
-        .collect();
+    if options.verbosity >= 1 {
+        println!(
+            "c {} variables, {} clauses, {} xors",
+            num_vars,
+            clauses.len(),
+            xors.len()
+        );
+    }
 
-    let mut neg: Vec<usize> = clauses
-        .iter()
-        .enumerate()
-        .filter(|(_, clause)| clause.contains(&-pivot))
-        .map(|e| e.0)
-        .collect();
+    // eliminating variables would ignore their occurrences in XOR constraints, which the proof
+    // does not cover either
+    if !xors.is_empty() && options.proof.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "proofs are not supported with XOR constraints",
+        ));
+    }
+
+    // the solvers of a portfolio import clauses the proof could not justify
+    if options.threads > 1 && options.proof.is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "proofs are not supported with several threads",
+        ));
+    }
+
+    let mut dp = DavisPutnam::new(clauses);
+
+    // variables occurring only in XOR constraints are part of the model as well
+    dp.set_num_vars(num_vars);
+    dp.set_terminator(terminator(options, start));
+
+    if let Some(proof) = open_proof(options)? {
+        dp.set_proof(proof);
+    }
+
+    let (eliminated, rounds) = if xors.is_empty() {
+        eliminate(&mut dp, options)
+    } else {
+        (None, vec![])
+    };
+
+    if eliminated.is_none() && options.verbosity >= 1 {
+        println!(
+            "c eliminated {} variables, {} clauses remain",
+            dp.eliminated(),
+            dp.clauses().len()
+        );
+    }
+
+    let search = if eliminated.is_none() && options.local_search {
+        Some(local_search(&dp, options))
+    } else {
+        None
+    };
+
+    let mut stats = Stats::default();
+
+    let (status, model) = match (eliminated, &search) {
+        (Some(Status::Sat), _) => (Status::Sat, dp.model()),
+
+        (Some(status), _) => (status, vec![]),
+
+        // local search ignores XOR constraints, so only its phases are of use with them
+        (None, Some((Status::Sat, search))) if xors.is_empty() => {
+            (Status::Sat, dp.reconstruct(&search.model()))
+        }
+
+        (None, _) if options.threads > 1 => {
+            let mut portfolio = Portfolio::new(options.threads);
 
-    let mut pos_count = 0;
-    let mut neg_count = 0;
+            portfolio.set_terminator(terminator(options, start));
 
-    for p in &pos {
-        for n in &neg {
-            if p == n {
-                continue;
+            for clause in dp.clauses() {
+                portfolio.add_clause(clause);
             }
 
-            let mut conflict: Vec<i32> = clauses[*p]
-                .iter()
-                .filter(|l| **l != pivot)
-                .chain(clauses[*n].iter().filter(|l| **l != -pivot))
-                .copied()
-                .collect();
+            for xor in &xors {
+                portfolio.add_xor(xor);
+            }
+
+            let status = portfolio.solve();
+
+            stats.merge(portfolio.stats());
+
+            if options.verbosity >= 1 {
+                if let Some(i) = portfolio.winner() {
+                    println!("c solver {} of the portfolio concluded", i);
+                }
+            }
+
+            match status {
+                Status::Sat => (Status::Sat, dp.reconstruct(portfolio.model())),
+                status => (status, vec![]),
+            }
+        }
+
+        (None, _) => {
+            let mut solver = Solver::new();
+
+            if let Some(seed) = options.seed {
+                solver.set_seed(seed);
+            }
+
+            solver.set_terminator(terminator(options, start));
+
+            if let Some((_, search)) = &search {
+                search.set_phases(&mut solver);
+            }
+
+            if let Some(proof) = dp.take_proof() {
+                solver.set_proof(proof);
+            }
+
+            for clause in dp.clauses() {
+                solver.add_clause(clause);
+            }
+
+            for xor in &xors {
+                solver.add_xor(xor);
+            }
+
+            let status = solver.solve();
+
+            stats.merge(solver.stats());
+
+            if let Some(proof) = solver.take_proof() {
+                dp.set_proof(proof);
+            }
+
+            match status {
+                Status::Sat => (Status::Sat, dp.reconstruct(&solver.model())),
+                status => (status, vec![]),
+            }
+        }
+    };
+
+    if let Some(proof) = dp.take_proof() {
+        proof.finish()?;
+    }
+
+    stats.merge(dp.stats());
+
+    if options.verbosity >= 1 {
+        print_stats(&stats);
+        println!("c solved in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    let model: Vec<i32> = (1..=num_vars as i32)
+        .map(|v| *model.get(v as usize - 1).unwrap_or(&-v))
+        .collect();
+
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("status", &status)
+            .field("variables", &num_vars)
+            .field("clauses", &num_clauses)
+            .field("xors", &xors.len())
+            .field("preprocessing", &preprocessing(&dp, &rounds));
 
-            conflict.sort_unstable();
-            conflict.dedup();
+        if let Some((_, search)) = &search {
+            report.field("flips", &search.flips());
+        }
+
+        if status == Status::Sat {
+            report.field("model", &model);
+        }
+
+        report
+            .field("stats", &stats)
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        write_solution(&mut out, status, &model)?;
+        write_output(options, &out)?;
+    }
+
+    Ok(match status {
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Unknown => 0,
+    })
+}
+
+fn maxsat(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut maxsat = MaxSat::new();
+
+    for clause in Wcnf::new(open_input(&options.inputs[0])?) {
+        maxsat.add(&clause);
+    }
+
+    maxsat.set_algorithm(options.algorithm);
+
+    if !options.json {
+        maxsat.on_bound(|cost| println!("o {}", cost));
+    }
+
+    maxsat.set_terminator(terminator(options, start));
 
-            conflicts.push(conflict);
+    let status = maxsat.solve();
+
+    if options.verbosity >= 1 {
+        println!("c solved in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    if options.json {
+        let mut report = report(options);
+        let found = maxsat.cost().is_some();
+
+        report
+            .field("status", &if found { Status::Sat } else { status })
+            .field("optimal", &(status == Status::Sat))
+            .field("cost", &maxsat.cost());
+
+        if found {
+            report.field("model", &maxsat.model());
         }
+
+        report.field("time", &start.elapsed());
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        write_maxsat_solution(&mut out, status, &maxsat.model())?;
+        write_output(options, &out)?;
     }
 
-    let mut start = 0;
+    Ok(match status {
+        Status::Sat => 30,
+        Status::Unsat => 20,
+        Status::Unknown if maxsat.cost().is_some() => 10,
+        Status::Unknown => 0,
+    })
+}
+
+fn qbf(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+    let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+    let (num_vars, num_clauses) = dimacs.header().unwrap_or((0, clauses.len()));
+
+    let mut qbf = Qbf::new(dimacs.prefix(), clauses);
 
-    let mut indexes: Vec<usize> = pos.iter().chain(neg.iter()).copied().collect();
+    qbf.set_terminator(terminator(options, start));
 
-    indexes.sort_unstable();
-    indexes.dedup();
+    let status = qbf.solve();
 
-    for index in indexes.iter().rev() {
-        clauses.swap_remove(*index);
+    if options.verbosity >= 1 {
+        println!("c solved in {:.3}s", start.elapsed().as_secs_f64());
     }
 
-    clauses.extend(conflicts);
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("status", &status)
+            .field("model", &qbf.model())
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        match status {
+            Status::Sat => writeln!(out, "s cnf 1 {} {}", num_vars, num_clauses)?,
+            Status::Unsat => writeln!(out, "s cnf 0 {} {}", num_vars, num_clauses)?,
+            Status::Unknown => writeln!(out, "s cnf -1 {} {}", num_vars, num_clauses)?,
+        }
+
+        for literal in qbf.model() {
+            writeln!(out, "V {} 0", literal)?;
+        }
+
+        write_output(options, &out)?;
+    }
+
+    Ok(match status {
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Unknown => 0,
+    })
+}
+
+fn pseudo_boolean(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut opb = Opb::new(open_input(&options.inputs[0])?);
+    let constraints: Vec<_> = opb.by_ref().collect();
+
+    let declared = opb.header().map(|h| h.0).unwrap_or(0);
+    let used = constraints
+        .iter()
+        .flat_map(|c| c.terms.iter())
+        .chain(opb.objective().unwrap_or(&[]))
+        .map(|(_, l)| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    let num_vars = declared.max(used);
+    let mut aux = num_vars;
+    let mut maxsat = MaxSat::new();
+
+    for constraint in &constraints {
+        for clause in pb::encode(constraint, options.encoding, &mut aux) {
+            maxsat.add_hard(&clause);
+        }
+    }
+
+    if options.verbosity >= 1 {
+        println!(
+            "c {} constraints encoded with {} auxiliary variables",
+            constraints.len(),
+            aux - num_vars
+        );
+    }
+
+    let (offset, soft) = pb::objective(opb.objective().unwrap_or(&[]));
+
+    for (weight, literal) in soft {
+        maxsat.add_soft(weight, &[literal]);
+    }
+
+    maxsat.set_algorithm(options.algorithm);
+
+    if opb.objective().is_some() && !options.json {
+        maxsat.on_bound(move |cost| println!("o {}", offset + cost as i64));
+    }
+
+    maxsat.set_terminator(terminator(options, start));
+
+    let status = maxsat.solve();
+
+    if options.verbosity >= 1 {
+        println!("c solved in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    let model: Vec<i32> = maxsat.model().into_iter().take(num_vars).collect();
+
+    if options.json {
+        let mut report = report(options);
+        let found = maxsat.cost().is_some();
+
+        report
+            .field("status", &if found { Status::Sat } else { status })
+            .field(
+                "optimal",
+                &(status == Status::Sat && opb.objective().is_some()),
+            );
+
+        if opb.objective().is_some() {
+            report.field("cost", &maxsat.cost().map(|cost| offset + cost as i64));
+        }
+
+        if found {
+            report.field("model", &model);
+        }
+
+        report
+            .field("constraints", &constraints.len())
+            .field("auxiliary_variables", &(aux - num_vars))
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        write_pb_solution(&mut out, status, opb.objective().is_some(), &model)?;
+        write_output(options, &out)?;
+    }
+
+    Ok(match status {
+        Status::Sat if opb.objective().is_some() => 30,
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Unknown if maxsat.cost().is_some() => 10,
+        Status::Unknown => 0,
+    })
+}
+
+fn mus(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let (num_vars, clauses) = read_cnf(options)?;
+
+    let mut mus = Mus::from_clauses(&clauses);
+
+    mus.set_terminator(terminator(options, start));
+
+    let subset = mus.mus();
+
+    if options.verbosity >= 1 {
+        println!("c extracted in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    if options.json {
+        let mut report = report(options);
+
+        match &subset {
+            Err(status) => report.field("status", status),
+
+            Ok(subset) => {
+                let indices: Vec<usize> = subset.iter().map(|i| i + 1).collect();
+
+                report
+                    .field("status", &Status::Unsat)
+                    .field("clauses", &indices)
+            }
+        };
+
+        report.field("time", &start.elapsed());
+        write_report(options, &report)?;
+
+        return Ok(match subset {
+            Ok(_) => 20,
+            Err(Status::Sat) => 10,
+            Err(_) => 0,
+        });
+    }
+
+    let mut out = Vec::new();
+
+    let code = match subset {
+        Err(Status::Sat) => {
+            writeln!(out, "s SATISFIABLE")?;
+            10
+        }
+
+        Err(_) => {
+            writeln!(out, "s UNKNOWN")?;
+            0
+        }
+
+        Ok(subset) => {
+            let selected: Vec<Vec<i32>> = subset.iter().map(|i| clauses[*i].clone()).collect();
+
+            write!(out, "c clauses")?;
+
+            for i in &subset {
+                write!(out, " {}", i + 1)?;
+            }
+
+            writeln!(out)?;
+            write_cnf(&mut out, num_vars, &selected)?;
+            20
+        }
+    };
+
+    write_output(options, &out)?;
+
+    Ok(code)
+}
+
+fn print_backbone(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let (num_vars, clauses) = read_cnf(options)?;
+    let mut solver = Solver::new();
+
+    solver.set_terminator(terminator(options, start));
+
+    for clause in &clauses {
+        solver.add_clause(clause);
+    }
+
+    let vars: Vec<i32> = (1..=num_vars as i32).collect();
+    let fixed = backbone(&mut solver, &vars);
+
+    if options.verbosity >= 1 {
+        println!("c computed in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    if options.json {
+        let mut report = report(options);
+
+        let status = match &fixed {
+            Ok(_) => Status::Sat,
+            Err(status) => *status,
+        };
+
+        report.field("status", &status);
+
+        if let Ok(fixed) = &fixed {
+            report.field("backbone", fixed);
+        }
+
+        report.field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+
+        return Ok(match status {
+            Status::Sat => 10,
+            Status::Unsat => 20,
+            Status::Unknown => 0,
+        });
+    }
+
+    let mut out = Vec::new();
+
+    let code = match fixed {
+        Ok(fixed) => {
+            writeln!(out, "s SATISFIABLE")?;
+            write!(out, "b")?;
+
+            for l in &fixed {
+                write!(out, " {}", l)?;
+            }
+
+            writeln!(out, " 0")?;
+            10
+        }
+
+        Err(Status::Unsat) => {
+            writeln!(out, "s UNSATISFIABLE")?;
+            20
+        }
+
+        Err(_) => {
+            writeln!(out, "s UNKNOWN")?;
+            0
+        }
+    };
+
+    write_output(options, &out)?;
+
+    Ok(code)
+}
+
+fn count(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+    let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+
+    no_xors(dimacs.xors())?;
+
+    if dimacs
+        .projection()
+        .is_some_and(|vars| vars.iter().any(|v| *v <= 0))
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "projected variables must be positive",
+        ));
+    }
+
+    let mut counter = Counter::new(dimacs.header().map(|h| h.0).unwrap_or(0));
+
+    counter.set_terminator(terminator(options, start));
+
+    for clause in &clauses {
+        counter.add_clause(clause);
+    }
+
+    if let Some(vars) = dimacs.projection() {
+        counter.set_projection(vars);
+    }
+
+    for (literal, weight) in dimacs.weights() {
+        counter.set_weight(*literal, weight.clone());
+    }
+
+    let weighted = !dimacs.weights().is_empty();
+
+    let count = if weighted {
+        counter.weighted_count()
+    } else {
+        Rational::from(counter.count())
+    };
+
+    if options.verbosity >= 1 {
+        println!("c counted in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    let kind = match (dimacs.projection(), weighted) {
+        (Some(_), true) => "pwmc",
+        (None, true) => "wmc",
+        (Some(_), false) => "pmc",
+        (None, false) => "mc",
+    };
+
+    if options.json {
+        let mut report = report(options);
+
+        let status = match counter.status() {
+            Status::Unknown => Status::Unknown,
+            _ if count.is_zero() => Status::Unsat,
+            _ => Status::Sat,
+        };
+
+        // counts are written as strings, as they may exceed the precision of JSON numbers
+        report
+            .field("status", &status)
+            .field("type", kind)
+            .field("count", &count.to_string())
+            .field("exact", &(status != Status::Unknown))
+            .field("conflicts", &counter.conflicts())
+            .field("decisions", &counter.decisions())
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+
+        return Ok(match status {
+            Status::Sat => 10,
+            Status::Unsat => 20,
+            Status::Unknown => 0,
+        });
+    }
+
+    let mut out = Vec::new();
+
+    if counter.status() == Status::Unknown {
+        writeln!(out, "s UNKNOWN")?;
+        writeln!(out, "c lower bound {}", count)?;
+        write_output(options, &out)?;
+
+        return Ok(0);
+    }
+
+    if count.is_zero() {
+        writeln!(out, "s UNSATISFIABLE")?;
+    } else {
+        writeln!(out, "s SATISFIABLE")?;
+    }
+
+    writeln!(out, "c s type {}", kind)?;
+
+    if weighted {
+        writeln!(out, "c s exact arb frac {}", count)?;
+    } else {
+        writeln!(out, "c s exact arb int {}", count)?;
+    }
+
+    write_output(options, &out)?;
+
+    Ok(if count.is_zero() { 20 } else { 10 })
+}
+
+fn approx_count(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+    let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+
+    if !dimacs.weights().is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "weighted instances can only be counted exactly",
+        ));
+    }
+
+    let mut counter = ApproxCounter::new(options.epsilon.unwrap(), options.delta);
+
+    counter.set_seed(options.seed.unwrap_or(0));
+    counter.set_terminator(terminator(options, start));
+
+    for clause in &clauses {
+        counter.add_clause(clause);
+    }
+
+    for xor in dimacs.xors() {
+        counter.add_xor(xor);
+    }
+
+    counter.set_projection(&projection(&dimacs, &clauses));
+
+    let count = counter.count();
+
+    if options.verbosity >= 1 {
+        println!("c counted in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    let kind = match dimacs.projection() {
+        Some(_) => "pmc",
+        None => "mc",
+    };
+
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("status", &counter.status())
+            .field("type", kind)
+            .field("count", &count.to_string())
+            .field("exact", &false)
+            .field("epsilon", &options.epsilon.unwrap())
+            .field("delta", &options.delta)
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+
+        return Ok(match counter.status() {
+            Status::Sat => 10,
+            Status::Unsat => 20,
+            Status::Unknown => 0,
+        });
+    }
+
+    let mut out = Vec::new();
+
+    match counter.status() {
+        Status::Sat => writeln!(out, "s SATISFIABLE")?,
+        Status::Unsat => writeln!(out, "s UNSATISFIABLE")?,
+
+        Status::Unknown => {
+            writeln!(out, "s UNKNOWN")?;
+            write_output(options, &out)?;
+
+            return Ok(0);
+        }
+    }
+
+    writeln!(out, "c s type {}", kind)?;
+    writeln!(out, "c s approx arb int {}", count)?;
+    write_output(options, &out)?;
+
+    Ok(if count.is_zero() { 20 } else { 10 })
+}
+
+fn sample(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+    let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+    let mut sampler = Sampler::new();
+
+    sampler.set_seed(options.seed.unwrap_or(0));
+    sampler.set_terminator(terminator(options, start));
+
+    for clause in &clauses {
+        sampler.add_clause(clause);
+    }
+
+    for xor in dimacs.xors() {
+        sampler.add_xor(xor);
+    }
+
+    let vars = projection(&dimacs, &clauses);
+
+    sampler.set_projection(&vars);
+
+    let mut samples = Vec::new();
+    let mut status = Status::Sat;
+
+    for _ in 0..options.samples {
+        let model = match sampler.sample() {
+            Ok(model) => model,
+
+            // the samples drawn so far still show the formula satisfiable
+            Err(Status::Unknown) if !samples.is_empty() => break,
+
+            Err(failed) => {
+                status = failed;
+                break;
+            }
+        };
+
+        let sample: Vec<i32> = vars.iter().map(|v| model[*v as usize - 1]).collect();
+
+        samples.push(sample);
+    }
+
+    if options.verbosity >= 1 {
+        println!("c sampled in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("status", &status)
+            .field("samples", &samples)
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        if !samples.is_empty() {
+            writeln!(out, "s SATISFIABLE")?;
+        }
+
+        for sample in &samples {
+            write!(out, "v")?;
+
+            for l in sample {
+                write!(out, " {}", l)?;
+            }
+
+            writeln!(out, " 0")?;
+        }
+
+        match status {
+            Status::Sat => {}
+            Status::Unsat => writeln!(out, "s UNSATISFIABLE")?,
+            Status::Unknown => writeln!(out, "s UNKNOWN")?,
+        }
+
+        write_output(options, &out)?;
+    }
+
+    Ok(match status {
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Unknown => 0,
+    })
+}
+
+fn cube(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let (_, clauses) = read_cnf(options)?;
+    let mut cuber = Cuber::new(&clauses);
+
+    cuber.set_depth(options.depth);
+    cuber.set_terminator(terminator(options, start));
+
+    let cubes = cuber.cubes();
+
+    if options.verbosity >= 1 {
+        println!(
+            "c {} cubes made in {:.3}s",
+            cubes.len(),
+            start.elapsed().as_secs_f64()
+        );
+    }
+
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("cubes", &cubes)
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        write_icnf(&mut out, &clauses, &cubes)?;
+        write_output(options, &out)?;
+    }
+
+    Ok(0)
+}
+
+fn conquer(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+    let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+
+    let num_vars = clauses
+        .iter()
+        .chain(dimacs.cubes().iter())
+        .flatten()
+        .map(|l| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    if options.verbosity >= 1 {
+        println!(
+            "c {} variables, {} clauses, {} cubes",
+            num_vars,
+            clauses.len(),
+            dimacs.cubes().len()
+        );
+    }
+
+    let mut conquer = Conquer::new(options.threads);
+
+    conquer.set_terminator(terminator(options, start));
+
+    for clause in &clauses {
+        conquer.add_clause(clause);
+    }
+
+    for cube in dimacs.cubes() {
+        conquer.add_cube(cube);
+    }
+
+    let status = conquer.solve();
+
+    if options.verbosity >= 1 {
+        println!("c solved in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    let model: Vec<i32> = (1..=num_vars as i32)
+        .map(|v| *conquer.model().get(v as usize - 1).unwrap_or(&-v))
+        .collect();
+
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("status", &status)
+            .field("variables", &num_vars)
+            .field("clauses", &clauses.len())
+            .field("cubes", &dimacs.cubes().len());
+
+        if status == Status::Sat {
+            report.field("model", &model);
+        }
+
+        report.field("time", &start.elapsed());
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        write_solution(&mut out, status, &model)?;
+        write_output(options, &out)?;
+    }
+
+    Ok(match status {
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Unknown => 0,
+    })
+}
+
+fn simplify(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let (num_vars, clauses) = read_cnf(options)?;
+    let num_clauses = clauses.len();
+    let mut dp = DavisPutnam::new(clauses);
+
+    dp.set_terminator(terminator(options, start));
+
+    if let Some(proof) = open_proof(options)? {
+        dp.set_proof(proof);
+    }
+
+    let (status, rounds) = eliminate(&mut dp, options);
+
+    if let Some(proof) = dp.take_proof() {
+        proof.finish()?;
+    }
+
+    if options.verbosity >= 1 {
+        println!(
+            "c eliminated {} variables, {} clauses remain",
+            dp.eliminated(),
+            dp.clauses().len()
+        );
+    }
+
+    if options.json {
+        let mut report = report(options);
+
+        report
+            .field("status", &status)
+            .field("variables", &num_vars)
+            .field("clauses", &num_clauses)
+            .field("preprocessing", &preprocessing(&dp, &rounds))
+            .field("formula", dp.clauses())
+            .field("stats", dp.stats())
+            .field("time", &start.elapsed());
+
+        write_report(options, &report)?;
+    } else {
+        let mut out = Vec::new();
+
+        write_cnf(&mut out, num_vars, dp.clauses())?;
+        write_output(options, &out)?;
+    }
+
+    Ok(0)
+}
+
+fn stats(options: &Options) -> io::Result<i32> {
+    let (num_vars, clauses) = read_cnf(options)?;
+
+    let literals: usize = clauses.iter().map(|c| c.len()).sum();
+    let positive = clauses.iter().flatten().filter(|l| **l > 0).count();
+    let longest = clauses.iter().map(|c| c.len()).max().unwrap_or(0);
+    let count = |n: usize| clauses.iter().filter(|c| c.len() == n).count();
+
+    if options.json {
+        let mut report = report(options);
+        let mean = literals as f64 / clauses.len() as f64;
+
+        report
+            .field("variables", &num_vars)
+            .field("clauses", &clauses.len())
+            .field("literals", &literals)
+            .field("positive_literals", &positive)
+            .field("negative_literals", &(literals - positive))
+            .field("empty_clauses", &count(0))
+            .field("unit_clauses", &count(1))
+            .field("binary_clauses", &count(2))
+            .field("ternary_clauses", &count(3))
+            .field("longest_clause", &longest)
+            .field("mean_clause_length", &mean);
+
+        write_report(options, &report)?;
+
+        return Ok(0);
+    }
+
+    let mut out = Vec::new();
+
+    writeln!(out, "variables: {}", num_vars)?;
+    writeln!(out, "clauses: {}", clauses.len())?;
+    writeln!(out, "literals: {}", literals)?;
+    writeln!(out, "positive literals: {}", positive)?;
+    writeln!(out, "negative literals: {}", literals - positive)?;
+    writeln!(out, "empty clauses: {}", count(0))?;
+    writeln!(out, "unit clauses: {}", count(1))?;
+    writeln!(out, "binary clauses: {}", count(2))?;
+    writeln!(out, "ternary clauses: {}", count(3))?;
+    writeln!(out, "longest clause: {}", longest)?;
+
+    if !clauses.is_empty() {
+        writeln!(
+            out,
+            "mean clause length: {:.2}",
+            literals as f64 / clauses.len() as f64
+        )?;
+    }
+
+    write_output(options, &out)?;
+
+    Ok(0)
+}
+
+/// The format of a file, told by its `p` line, or by `h` lines for WCNF files in the 2022
+/// format, which have none.
+fn detect_format(data: &[u8]) -> Format {
+    let text = String::from_utf8_lossy(data);
+    let mut lines = text.lines().map(|line| line.trim());
+
+    let header = lines
+        .clone()
+        .find(|line| line.starts_with('p'))
+        .map(|line| line.split_whitespace().nth(1).unwrap_or(""));
+
+    match header {
+        Some("wcnf") => Format::WcnfLegacy,
+        Some("inccnf") => Format::Icnf,
+        None if lines.any(|line| line.starts_with("h ")) => Format::Wcnf,
+
+        // the prefix comes before any clause
+        _ => match lines.find(|line| !(line.is_empty() || line.starts_with(['c', 'p']))) {
+            Some(line) if line.starts_with(['e', 'a']) => Format::Qdimacs,
+            _ => Format::Cnf,
+        },
+    }
+}
+
+/// An error for a conversion that would lose part of the input.
+fn lossy(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("{} cannot be written in the output format", what),
+    )
+}
+
+fn convert(options: &Options) -> io::Result<i32> {
+    let mut data = Vec::new();
+
+    open_input(&options.inputs[0])?.read_to_end(&mut data)?;
+
+    let from = detect_format(&data);
+    let to = options.format.unwrap_or(from);
+    let mut out = Vec::new();
+
+    if let Format::Wcnf | Format::WcnfLegacy = from {
+        let clauses: Vec<WeightedClause> = Wcnf::new(&data[..]).collect();
+
+        let num_vars = clauses
+            .iter()
+            .flat_map(|c| c.lits.iter())
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+
+        let hard: Vec<Vec<i32>> = clauses
+            .iter()
+            .filter(|c| c.weight.is_none())
+            .map(|c| c.lits.clone())
+            .collect();
+
+        match to {
+            Format::Wcnf => write_wcnf(&mut out, &clauses)?,
+            Format::WcnfLegacy => write_wcnf_legacy(&mut out, num_vars, &clauses)?,
+            _ if hard.len() < clauses.len() => return Err(lossy("soft clauses")),
+            Format::Cnf => write_cnf(&mut out, num_vars, &hard)?,
+            Format::Qdimacs => write_qdimacs(&mut out, num_vars, &[], &hard)?,
+            Format::Icnf => write_icnf(&mut out, &hard, &[])?,
+        }
+    } else {
+        let mut dimacs = Dimacs::new(&data[..]);
+        let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+
+        no_xors(dimacs.xors())?;
+
+        let declared = dimacs.header().map(|h| h.0).unwrap_or(0);
+        let used = clauses
+            .iter()
+            .chain(dimacs.cubes())
+            .flatten()
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+
+        let num_vars = declared.max(used);
+
+        if !dimacs.prefix().is_empty() && to != Format::Qdimacs {
+            return Err(lossy("quantifiers"));
+        }
+
+        if !dimacs.cubes().is_empty() && to != Format::Icnf {
+            return Err(lossy("cubes"));
+        }
+
+        let hard = |lits: &Vec<i32>| WeightedClause {
+            weight: None,
+            lits: lits.clone(),
+        };
+
+        match to {
+            Format::Cnf => write_cnf(&mut out, num_vars, &clauses)?,
+            Format::Qdimacs => write_qdimacs(&mut out, num_vars, dimacs.prefix(), &clauses)?,
+            Format::Icnf => write_icnf(&mut out, &clauses, dimacs.cubes())?,
+
+            Format::Wcnf => {
+                let clauses: Vec<WeightedClause> = clauses.iter().map(hard).collect();
+
+                write_wcnf(&mut out, &clauses)?
+            }
+
+            Format::WcnfLegacy => {
+                let clauses: Vec<WeightedClause> = clauses.iter().map(hard).collect();
+
+                write_wcnf_legacy(&mut out, num_vars, &clauses)?
+            }
+        }
+    }
+
+    write_output(options, &out)?;
+
+    Ok(0)
+}
+
+fn verify(options: &Options) -> io::Result<i32> {
+    let (status, model) = read_solution(open_input(&options.inputs[1])?)?;
+
+    if options.json {
+        let mut report = report(options);
+
+        report.field("status", &status);
+
+        let (falsified, falsified_xor) = match status {
+            Status::Sat => falsified(options, &model)?,
+            _ => (None, None),
+        };
+
+        let verified = status == Status::Sat && falsified.is_none() && falsified_xor.is_none();
+
+        report
+            .field("verified", &verified)
+            .field("falsified", &falsified.as_ref().map(|(index, _)| index + 1))
+            .field("clause", &falsified.map(|(_, clause)| clause))
+            .field(
+                "falsified_xor",
+                &falsified_xor.as_ref().map(|(index, _)| index + 1),
+            )
+            .field("xor", &falsified_xor.map(|(_, xor)| xor));
+
+        write_report(options, &report)?;
+
+        return Ok(if verified { 0 } else { 1 });
+    }
+
+    if status != Status::Sat {
+        println!("c no model to verify");
+        return Ok(1);
+    }
+
+    match falsified(options, &model)? {
+        (None, None) => {
+            println!("c model satisfies all clauses and XOR constraints");
+            Ok(0)
+        }
+
+        (Some((index, clause)), _) => {
+            let clause: Vec<String> = clause.iter().map(|l| l.to_string()).collect();
+
+            println!(
+                "c clause {} is falsified: {} 0",
+                index + 1,
+                clause.join(" ")
+            );
+            Ok(1)
+        }
+
+        (None, Some((index, xor))) => {
+            let xor: Vec<String> = xor.iter().map(|l| l.to_string()).collect();
+
+            println!(
+                "c XOR constraint {} is falsified: x{} 0",
+                index + 1,
+                xor.join(" ")
+            );
+            Ok(1)
+        }
+    }
+}
+
+/// The index and contents of a clause or XOR constraint a model falsifies, if any.
+type Falsified = Option<(usize, Vec<i32>)>;
+
+/// The first clause of the input the model falsifies, or else its first falsified XOR
+/// constraint, each with its index.
+fn falsified(options: &Options, model: &[i32]) -> io::Result<(Falsified, Falsified)> {
+    let mut dimacs = Dimacs::new(open_input(&options.inputs[0])?);
+
+    // the XOR constraints are only all known once every clause has been read
+    match rsat::solution::verify(dimacs.by_ref(), model) {
+        Some(clause) => Ok((Some(clause), None)),
+        None => Ok((None, rsat::solution::verify_xors(dimacs.xors(), model))),
+    }
+}
+
+fn check(options: &Options) -> io::Result<i32> {
+    let start = Instant::now();
+    let (num_vars, formula) = read_cnf(options)?;
+    let proof = open_input(&options.inputs[1])?;
+
+    let result = if options.lrat {
+        check_lrat(&formula, &read_lrat(proof)?).map(|_| None)
+    } else {
+        check_drat(&formula, &read_drat(proof)?).map(Some)
+    };
+
+    if options.verbosity >= 1 {
+        println!("c checked in {:.3}s", start.elapsed().as_secs_f64());
+    }
+
+    if options.json {
+        let mut report = report(options);
+
+        let error = match &result {
+            Ok(_) => None,
+            Err(CheckError::NoConflict) => Some("proof does not refute the formula".to_string()),
+            Err(CheckError::Lemma(k)) => {
+                Some(format!("proof step {} is neither RUP nor RAT", k + 1))
+            }
+            Err(CheckError::Hint(id)) => Some(format!("hints of clause {} are invalid", id)),
+        };
+
+        report
+            .field("verified", &error.is_none())
+            .field("error", &error);
+
+        if let Ok(Some(certificate)) = &result {
+            report
+                .field("core", &certificate.core.len())
+                .field("lemmas", &certificate.lrat.len());
+        }
+
+        report.field("time", &start.elapsed());
+        write_report(options, &report)?;
+
+        if error.is_some() {
+            return Ok(1);
+        }
+    }
+
+    let certificate = match result {
+        Ok(certificate) => certificate,
+        Err(error) => {
+            match error {
+                CheckError::NoConflict => println!("c proof does not refute the formula"),
+                CheckError::Lemma(k) => println!("c proof step {} is neither RUP nor RAT", k + 1),
+                CheckError::Hint(id) => println!("c hints of clause {} are invalid", id),
+            }
+
+            println!("s NOT VERIFIED");
+            return Ok(1);
+        }
+    };
+
+    if let Some(certificate) = certificate {
+        if options.verbosity >= 1 {
+            println!(
+                "c core of {} clauses, {} lemmas",
+                certificate.core.len(),
+                certificate.lrat.len()
+            );
+        }
+
+        if let Some(path) = &options.proof {
+            let mut out = BufWriter::new(File::create(path)?);
+
+            write_lrat(&mut out, &certificate.lrat)?;
+            out.flush()?;
+        }
+
+        if let Some(path) = &options.core {
+            let core: Vec<Vec<i32>> = certificate
+                .core
+                .iter()
+                .map(|c| formula[*c].clone())
+                .collect();
+
+            write_cnf(&mut BufWriter::new(File::create(path)?), num_vars, &core)?;
+        }
+    }
 
-    for clause in clauses.iter_mut() {
-        clause.sort_unstable();
-        clause.dedup();
+    if !options.json {
+        println!("s VERIFIED");
     }
 
-    clauses.sort_unstable();
-    clauses.dedup();
+    Ok(0)
 }
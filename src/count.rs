@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+
+use crate::bigint::{BigUint, Rational};
+use crate::encoder::Encoder;
+use crate::solver::Solver;
+use crate::terminator::Terminator;
+use crate::words::Word;
+use crate::Status;
+
+/// Exact model counter. Counts by DPLL search, splitting the clauses left after unit propagation
+/// into connected components counted separately and caching the count of each component.
+///
+/// With a projection, the distinct assignments to the projected variables that extend to a model
+/// are counted instead. Search then only branches on projected variables, and a component
+/// without any counts one if satisfiable.
+///
+/// Weighted counting sums the product of the weights of the literals of each model, or of each
+/// projected assignment, instead of counting one per model. Literals without a weight weigh one.
+///
+/// A count stopped by a terminator counts the branches it has not finished as zero, and so is a
+/// lower bound.
+pub struct Counter {
+    num_vars: usize,
+    clauses: Vec<Vec<i32>>,
+    projection: Option<Vec<i32>>,
+    weights: HashMap<i32, Rational>,
+    terminator: Option<Terminator>,
+
+    weighted: bool,
+    projected: Vec<bool>,
+    cache: HashMap<Vec<Vec<i32>>, Rational>,
+    conflicts: u64,
+    decisions: u64,
+    status: Status,
+}
+
+impl Counter {
+    /// Create a counter over the variables `1..=num_vars`, more being added as clauses use them.
+    pub fn new(num_vars: usize) -> Counter {
+        Counter {
+            num_vars,
+            clauses: Vec::new(),
+            projection: None,
+            weights: HashMap::new(),
+            terminator: None,
+
+            weighted: false,
+            projected: Vec::new(),
+            cache: HashMap::new(),
+            conflicts: 0,
+            decisions: 0,
+            status: Status::Unknown,
+        }
+    }
+
+    /// The number of variables counted over, unless projected.
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// Add a clause.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        let mut clause = clause.to_vec();
+
+        clause.sort_unstable_by_key(|l| (l.abs(), *l));
+        clause.dedup();
+
+        if let Some(max) = clause.iter().map(|l| l.unsigned_abs() as usize).max() {
+            self.num_vars = self.num_vars.max(max);
+        }
+
+        if clause.windows(2).any(|w| w[0] == -w[1]) {
+            return;
+        }
+
+        self.clauses.push(clause);
+    }
+
+    /// Count the assignments to the given variables only, such as those of a `c ind` line. The
+    /// variables must be positive.
+    pub fn set_projection(&mut self, vars: &[i32]) {
+        assert!(
+            vars.iter().all(|v| *v > 0),
+            "projected variables must be positive"
+        );
+
+        if let Some(max) = vars.iter().map(|v| *v as usize).max() {
+            self.num_vars = self.num_vars.max(max);
+        }
+
+        self.projection = Some(vars.to_vec());
+    }
+
+    /// Set the weight of a literal, such as given by a `c p weight` line.
+    pub fn set_weight(&mut self, literal: i32, weight: Rational) {
+        self.num_vars = self.num_vars.max(literal.unsigned_abs() as usize);
+        self.weights.insert(literal, weight);
+    }
+
+    /// Give up once the terminator stops, its conflict limit counting the branches found to
+    /// have no model.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Add the clauses encoded so far, and count the values of the given words only. Native XOR
+    /// constraints are not supported.
+    pub fn project_words(&mut self, encoder: &mut Encoder, words: &[&Word]) {
+        let vars: Vec<i32> = words
+            .iter()
+            .flat_map(|w| encoder.word(w))
+            .map(|l| l.abs())
+            .collect();
+
+        for clause in encoder.take_clauses() {
+            self.add_clause(&clause);
+        }
+
+        assert!(encoder.take_xors().is_empty());
+
+        self.num_vars = self.num_vars.max(encoder.num_vars());
+        self.set_projection(&vars);
+    }
+
+    /// The number of models, or of projected assignments.
+    pub fn count(&mut self) -> BigUint {
+        self.weighted = false;
+        self.run().to_integer().unwrap()
+    }
+
+    /// The total weight of the models, or of the projected assignments.
+    pub fn weighted_count(&mut self) -> Rational {
+        self.weighted = true;
+        self.run()
+    }
+
+    /// The outcome of the last count: `Status::Unknown` if stopped by the terminator, and
+    /// otherwise whether the count is positive.
+    pub fn status(&self) -> Status {
+        self.status
+    }
+
+    /// The number of branches of the last count found to have no model.
+    pub fn conflicts(&self) -> u64 {
+        self.conflicts
+    }
+
+    /// The number of branches of the last count.
+    pub fn decisions(&self) -> u64 {
+        self.decisions
+    }
+
+    fn run(&mut self) -> Rational {
+        let mut free: Vec<i32> = match &self.projection {
+            None => (1..=self.num_vars as i32).collect(),
+            Some(vars) => vars.clone(),
+        };
+
+        free.sort_unstable();
+        free.dedup();
+
+        self.projected = vec![false; self.num_vars + 1];
+
+        for v in &free {
+            self.projected[*v as usize] = true;
+        }
+
+        self.cache.clear();
+        self.conflicts = 0;
+        self.decisions = 0;
+        self.status = Status::Sat;
+
+        let count = self.search(self.clauses.clone(), free);
+
+        if count.is_zero() && self.status == Status::Sat {
+            self.status = Status::Unsat;
+        }
+
+        count
+    }
+
+    /// Whether the terminator stopped the count, in which case the status is unknown.
+    fn stopped(&mut self) -> bool {
+        if self.status == Status::Unknown {
+            return true;
+        }
+
+        if let Some(terminator) = &self.terminator {
+            if terminator.should_stop(self.conflicts)
+                || (self.decisions.is_multiple_of(1024) && terminator.out_of_memory())
+            {
+                self.status = Status::Unknown;
+            }
+        }
+
+        self.status == Status::Unknown
+    }
+
+    fn weight(&self, literal: i32) -> Rational {
+        match self.weights.get(&literal) {
+            Some(w) if self.weighted => w.clone(),
+            _ => Rational::one(),
+        }
+    }
+
+    /// Count the assignments to the `free` variables extending to models of the clauses. The
+    /// free variables include those of the clauses that are projected.
+    fn search(&mut self, clauses: Vec<Vec<i32>>, free: Vec<i32>) -> Rational {
+        if self.stopped() {
+            return Rational::zero();
+        }
+
+        let (clauses, assigned) = match propagate(clauses) {
+            Some(result) => result,
+
+            None => {
+                self.conflicts += 1;
+                return Rational::zero();
+            }
+        };
+
+        let mut occurs = vec![false; self.num_vars + 1];
+
+        for l in clauses.iter().flatten().chain(assigned.iter()) {
+            occurs[l.unsigned_abs() as usize] = true;
+        }
+
+        let mut count = Rational::one();
+
+        for l in assigned
+            .iter()
+            .filter(|l| self.projected[l.unsigned_abs() as usize])
+        {
+            count = &count * &self.weight(*l);
+        }
+
+        // projected variables not occurring in any clause take either value
+        let unconstrained: Vec<i32> = free
+            .iter()
+            .copied()
+            .filter(|v| !occurs[*v as usize])
+            .collect();
+
+        if self.weighted {
+            for v in unconstrained {
+                count = &count * &(&self.weight(v) + &self.weight(-v));
+            }
+        } else {
+            count = &count * &Rational::from(BigUint::pow2(unconstrained.len()));
+        }
+
+        for component in components(clauses) {
+            let n = match self.cache.get(&component) {
+                Some(n) => n.clone(),
+
+                None => {
+                    let n = self.count_component(&component);
+
+                    // a stopped count is only a lower bound
+                    if self.status != Status::Unknown {
+                        self.cache.insert(component, n.clone());
+                    }
+
+                    n
+                }
+            };
+
+            if n.is_zero() {
+                return n;
+            }
+
+            count = &count * &n;
+        }
+
+        count
+    }
+
+    fn count_component(&mut self, component: &[Vec<i32>]) -> Rational {
+        let mut occurrences: HashMap<i32, usize> = HashMap::new();
+
+        for l in component.iter().flatten() {
+            if self.projected[l.unsigned_abs() as usize] {
+                *occurrences.entry(l.abs()).or_insert(0) += 1;
+            }
+        }
+
+        let free: Vec<i32> = occurrences.keys().copied().collect();
+
+        let pivot = match occurrences.iter().max_by_key(|(v, n)| (**n, -**v)) {
+            Some((v, _)) => *v,
+
+            None => {
+                let mut solver = Solver::new();
+
+                if let Some(terminator) = &self.terminator {
+                    solver.set_terminator(terminator.clone());
+                }
+
+                for clause in component {
+                    solver.add_clause(clause);
+                }
+
+                return match solver.solve() {
+                    Status::Sat => Rational::one(),
+                    Status::Unsat => Rational::zero(),
+
+                    Status::Unknown => {
+                        self.status = Status::Unknown;
+                        Rational::zero()
+                    }
+                };
+            }
+        };
+
+        let mut count = Rational::zero();
+
+        self.decisions += 1;
+
+        for l in [pivot, -pivot] {
+            let mut clauses = component.to_vec();
+
+            clauses.push(vec![l]);
+            count = &count + &self.search(clauses, free.clone());
+        }
+
+        count
+    }
+}
+
+/// Unit propagation, returning the remaining clauses without their false literals and the
+/// literals assigned, or `None` on a conflict.
+fn propagate(mut clauses: Vec<Vec<i32>>) -> Option<(Vec<Vec<i32>>, Vec<i32>)> {
+    let mut assigned = Vec::new();
+
+    while let Some(unit) = clauses.iter().find(|c| c.len() <= 1) {
+        let l = *unit.first()?;
+
+        assigned.push(l);
+
+        clauses = clauses
+            .into_iter()
+            .filter(|c| !c.contains(&l))
+            .map(|c| c.into_iter().filter(|k| *k != -l).collect())
+            .collect();
+    }
+
+    Some((clauses, assigned))
+}
+
+/// Split clauses into groups sharing no variable, each sorted so that equal components compare
+/// equal.
+fn components(clauses: Vec<Vec<i32>>) -> Vec<Vec<Vec<i32>>> {
+    let mut parent: HashMap<i32, i32> = HashMap::new();
+
+    fn find(parent: &mut HashMap<i32, i32>, v: i32) -> i32 {
+        let p = *parent.entry(v).or_insert(v);
+
+        if p == v {
+            return v;
+        }
+
+        let root = find(parent, p);
+
+        parent.insert(v, root);
+        root
+    }
+
+    for clause in &clauses {
+        let first = find(&mut parent, clause[0].abs());
+
+        for l in &clause[1..] {
+            let root = find(&mut parent, l.abs());
+
+            parent.insert(root, first);
+        }
+    }
+
+    let mut groups: HashMap<i32, Vec<Vec<i32>>> = HashMap::new();
+
+    for clause in clauses {
+        let root = find(&mut parent, clause[0].abs());
+
+        groups.entry(root).or_default().push(clause);
+    }
+
+    let mut components: Vec<Vec<Vec<i32>>> = groups.into_values().collect();
+
+    for component in components.iter_mut() {
+        component.sort_unstable();
+    }
+
+    components
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::testing::random_3sat;
+    use crate::words::Bits;
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(11);
+
+        for round in 0..40 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 12, 8 + round % 30);
+
+            let models: Vec<u32> = (0u32..1 << 12)
+                .filter(|bits| {
+                    clauses.iter().all(|c| {
+                        c.iter()
+                            .any(|l| (bits >> (l.abs() - 1) & 1 == 1) == (*l > 0))
+                    })
+                })
+                .collect();
+
+            let mut counter = Counter::new(12);
+
+            for clause in &clauses {
+                counter.add_clause(clause);
+            }
+
+            assert_eq!(counter.count(), BigUint::from(models.len() as u64));
+
+            // projected onto the first five variables
+            let mut projected: Vec<u32> = models.iter().map(|m| m & 0x1f).collect();
+
+            projected.sort_unstable();
+            projected.dedup();
+
+            counter.set_projection(&[1, 2, 3, 4, 5]);
+
+            assert_eq!(counter.count(), BigUint::from(projected.len() as u64));
+
+            // a projected variable in no clause doubles the count
+            counter.set_projection(&[1, 2, 3, 4, 5, 14]);
+
+            assert_eq!(counter.count(), BigUint::from(2 * projected.len() as u64));
+        }
+    }
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 8);
+        let b = Word::var(&bits, 8);
+        let target = Word::from_u64(&bits, 8, 143);
+        let differs = (&(&a * &b) ^ &target).any();
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut counter = Counter::new(0);
+
+        counter.add_clause(&[-encoder.lit(differs.ids()[0])]);
+
+        // every odd a has exactly one b with a * b = 143 modulo 256
+        counter.project_words(&mut encoder, &[&a, &b]);
+
+        assert_eq!(counter.count(), BigUint::from(128));
+    }
+
+    #[test]
+    fn weighted_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 4);
+        let five = Word::from_u64(&bits, 4, 5);
+        let less = a.less_than(&five);
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut counter = Counter::new(0);
+
+        counter.add_clause(&[encoder.lit(less.ids()[0])]);
+        counter.project_words(&mut encoder, &[&a]);
+
+        // each bit of a is set with probability 1/4
+        for l in encoder.word(&a) {
+            counter.set_weight(l, "0.25".parse().unwrap());
+            counter.set_weight(-l, "3/4".parse().unwrap());
+        }
+
+        // a < 5 when bit 3 is clear, and either bit 2 is clear or bits 1 and 0 are as well:
+        // 3/4 * (3/4 + 1/4 * 9/16)
+        let p = counter.weighted_count();
+
+        assert_eq!(p.to_string(), "171/256");
+        assert_eq!(counter.count(), BigUint::from(5));
+        assert_eq!(counter.status(), Status::Sat);
+    }
+
+    #[test]
+    fn terminator_01() {
+        let mut rng = Rng::new(14);
+
+        let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 30, 100);
+
+        let mut counter = Counter::new(0);
+
+        for clause in &clauses {
+            counter.add_clause(clause);
+        }
+
+        let exact = counter.count();
+        let mut terminator = Terminator::new();
+
+        terminator.set_max_conflicts(3);
+        counter.set_terminator(terminator);
+
+        // stopped early, with a lower bound
+        let bound = counter.count();
+
+        assert_eq!(counter.status(), Status::Unknown);
+        assert!(counter.conflicts() >= 3);
+        assert!(bound < exact);
+
+        let terminator = Terminator::new();
+
+        terminator.terminate();
+        counter.set_terminator(terminator);
+
+        assert_eq!(counter.count(), BigUint::zero());
+        assert_eq!(counter.status(), Status::Unknown);
+    }
+}
@@ -0,0 +1,535 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::solver::{index, Solver};
+use crate::terminator::Terminator;
+use crate::Status;
+
+/// Lookahead cuber in the style of march, splitting the clauses into cubes to be solved
+/// separately, such as by `Conquer`. Each node of the split tree looks ahead on the most
+/// promising free variables, assigning each both ways and measuring how much unit propagation
+/// reduces the clauses. Literals whose propagation fails are assigned the other way, and the
+/// variable with the largest product of reductions is branched on.
+///
+/// The cubes are the decisions of the leaves, taken after a fixed number of decisions or once
+/// every clause is satisfied. Leaves refuted by propagation are dropped, so the clauses are
+/// satisfiable exactly if one of the cubes is.
+pub struct Cuber {
+    num_vars: usize,
+    clauses: Vec<Vec<i32>>,
+    occurs: Vec<Vec<usize>>,
+    depth: usize,
+    candidates: usize,
+    terminator: Option<Terminator>,
+}
+
+/// Partial assignment of a cuber, undone by truncating the trail.
+struct Assignment {
+    values: Vec<i8>,
+    trail: Vec<i32>,
+}
+
+impl Assignment {
+    fn value(&self, l: i32) -> i8 {
+        let v = self.values[l.unsigned_abs() as usize];
+
+        if l < 0 {
+            -v
+        } else {
+            v
+        }
+    }
+
+    fn assign(&mut self, l: i32) {
+        self.values[l.unsigned_abs() as usize] = if l < 0 { -1 } else { 1 };
+        self.trail.push(l);
+    }
+
+    fn undo(&mut self, len: usize) {
+        for l in self.trail.drain(len..) {
+            self.values[l.unsigned_abs() as usize] = 0;
+        }
+    }
+}
+
+impl Cuber {
+    /// Create a cuber over the given clauses, making cubes of up to 10 decisions.
+    pub fn new(clauses: &[Vec<i32>]) -> Cuber {
+        let num_vars = clauses
+            .iter()
+            .flatten()
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+
+        let mut occurs = vec![Vec::new(); 2 * num_vars];
+
+        for (c, clause) in clauses.iter().enumerate() {
+            for l in clause {
+                occurs[index(*l)].push(c);
+            }
+        }
+
+        Cuber {
+            num_vars,
+            clauses: clauses.to_vec(),
+            occurs,
+            depth: 10,
+            candidates: 20,
+            terminator: None,
+        }
+    }
+
+    /// Make cubes of up to `depth` decisions, so up to `2^depth` cubes.
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
+    /// Look ahead on up to `candidates` variables at each node, 20 by default.
+    pub fn set_candidates(&mut self, candidates: usize) {
+        self.candidates = candidates.max(1);
+    }
+
+    /// Stop splitting once the terminator stops, leaving the nodes not split yet as cubes so
+    /// that the cubes still cover the clauses. The conflict limit does not apply.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// The cubes, each as a list of literals to assume.
+    pub fn cubes(&self) -> Vec<Vec<i32>> {
+        let mut assignment = Assignment {
+            values: vec![0; self.num_vars + 1],
+            trail: Vec::new(),
+        };
+
+        let mut cubes = Vec::new();
+
+        // units of the clauses, including empty clauses as conflicts
+        for clause in &self.clauses {
+            match clause[..] {
+                [] => return cubes,
+                [l] if assignment.value(l) == -1 => return cubes,
+                [l] if assignment.value(l) == 0 => assignment.assign(l),
+                _ => (),
+            }
+        }
+
+        if self.propagate(&mut assignment, 0) {
+            self.split(&mut assignment, &mut Vec::new(), &mut cubes);
+        }
+
+        cubes
+    }
+
+    fn split(
+        &self,
+        assignment: &mut Assignment,
+        decisions: &mut Vec<i32>,
+        cubes: &mut Vec<Vec<i32>>,
+    ) {
+        let stopped = self
+            .terminator
+            .as_ref()
+            .is_some_and(|t| t.should_stop(0) || t.out_of_memory());
+
+        if decisions.len() >= self.depth || stopped {
+            cubes.push(decisions.clone());
+            return;
+        }
+
+        let v = match self.lookahead(assignment) {
+            // refuted
+            None => return,
+
+            // every clause is satisfied
+            Some(None) => {
+                cubes.push(decisions.clone());
+                return;
+            }
+
+            Some(Some(v)) => v,
+        };
+
+        for l in [v, -v] {
+            let mark = assignment.trail.len();
+
+            assignment.assign(l);
+
+            if self.propagate(assignment, mark) {
+                decisions.push(l);
+                self.split(assignment, decisions, cubes);
+                decisions.pop();
+            }
+
+            assignment.undo(mark);
+        }
+    }
+
+    /// Look ahead on the candidate variables, assigning failed literals the other way. Returns
+    /// `None` if the node is refuted, and otherwise the variable to branch on, if any is left.
+    /// The failed literals assigned are kept, as they are implied at the node.
+    fn lookahead(&self, assignment: &mut Assignment) -> Option<Option<i32>> {
+        'restart: loop {
+            let mut best = None;
+
+            for v in self.candidates(assignment) {
+                let mut reductions = [0.0; 2];
+
+                for (i, l) in [v, -v].iter().enumerate() {
+                    let mark = assignment.trail.len();
+
+                    assignment.assign(*l);
+
+                    let ok = self.propagate(assignment, mark);
+
+                    if ok {
+                        reductions[i] = self.reduction(assignment, mark);
+                    }
+
+                    assignment.undo(mark);
+
+                    if !ok {
+                        // a failed literal, which is false at this node
+                        let mark = assignment.trail.len();
+
+                        assignment.assign(-l);
+
+                        if !self.propagate(assignment, mark) {
+                            return None;
+                        }
+
+                        continue 'restart;
+                    }
+                }
+
+                let [left, right] = reductions;
+                let score = 1024.0 * left * right + left + right;
+
+                if best.is_none_or(|(_, s)| score > s) {
+                    best = Some((v, score));
+                }
+            }
+
+            return Some(best.map(|(v, _)| v));
+        }
+    }
+
+    /// The free variables of the unsatisfied clauses occurring most, weighing occurrences in
+    /// shorter clauses more.
+    fn candidates(&self, assignment: &Assignment) -> Vec<i32> {
+        let mut scores = vec![0.0; self.num_vars + 1];
+
+        for clause in &self.clauses {
+            if clause.iter().any(|l| assignment.value(*l) == 1) {
+                continue;
+            }
+
+            let free = clause.iter().filter(|l| assignment.value(**l) == 0).count();
+
+            for l in clause.iter().filter(|l| assignment.value(**l) == 0) {
+                scores[l.unsigned_abs() as usize] += 1.0 / (1 << free.min(30)) as f64;
+            }
+        }
+
+        let mut vars: Vec<i32> = (1..=self.num_vars as i32)
+            .filter(|v| scores[*v as usize] > 0.0)
+            .collect();
+
+        vars.sort_by(|a, b| {
+            scores[*b as usize]
+                .partial_cmp(&scores[*a as usize])
+                .unwrap()
+        });
+
+        vars.truncate(self.candidates);
+        vars
+    }
+
+    /// Unit propagation of the literals assigned since `mark`, returning false on a conflict.
+    fn propagate(&self, assignment: &mut Assignment, mark: usize) -> bool {
+        let mut head = mark;
+
+        while head < assignment.trail.len() {
+            let l = assignment.trail[head];
+
+            head += 1;
+
+            for c in &self.occurs[index(-l)] {
+                let clause = &self.clauses[*c];
+
+                if clause.iter().any(|k| assignment.value(*k) == 1) {
+                    continue;
+                }
+
+                let mut free = clause.iter().filter(|k| assignment.value(**k) == 0);
+
+                match (free.next(), free.next()) {
+                    (None, _) => return false,
+                    (Some(k), None) => assignment.assign(*k),
+                    _ => (),
+                }
+            }
+        }
+
+        true
+    }
+
+    /// How much the literals assigned since `mark` reduce the clauses, weighing the clauses
+    /// left unsatisfied with fewer free literals more, as they are closer to propagating.
+    fn reduction(&self, assignment: &Assignment, mark: usize) -> f64 {
+        let mut reduced: Vec<usize> = assignment.trail[mark..]
+            .iter()
+            .flat_map(|l| self.occurs[index(-l)].iter().copied())
+            .collect();
+
+        reduced.sort_unstable();
+        reduced.dedup();
+
+        reduced
+            .iter()
+            .map(|c| &self.clauses[*c])
+            .filter(|clause| clause.iter().all(|k| assignment.value(*k) != 1))
+            .map(|clause| {
+                let free = clause.iter().filter(|k| assignment.value(**k) == 0).count();
+
+                1.0 / (1 << free.min(30)) as f64
+            })
+            .sum()
+    }
+}
+
+/// Conquer driver for cubes, solving the clauses under the assumptions of each cube in turn
+/// with worker threads each running a solver of its own. The clauses are satisfiable exactly
+/// if one of the cubes is, so the first model found stops the workers, while the clauses are
+/// unsatisfiable once every cube is refuted. Without any cube, the clauses are solved as is.
+pub struct Conquer {
+    threads: usize,
+    clauses: Vec<Vec<i32>>,
+    cubes: Vec<Vec<i32>>,
+    deadline: Option<Instant>,
+    terminator: Option<Terminator>,
+
+    model: Vec<i32>,
+}
+
+impl Conquer {
+    /// Create a conquer driver with the given number of worker threads.
+    pub fn new(threads: usize) -> Conquer {
+        assert!(threads > 0);
+
+        Conquer {
+            threads,
+            clauses: Vec::new(),
+            cubes: Vec::new(),
+            deadline: None,
+            terminator: None,
+
+            model: Vec::new(),
+        }
+    }
+
+    /// Give up and report `Status::Unknown` once the deadline has passed.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops, its conflict limit
+    /// applying to each cube.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Add a clause.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        self.clauses.push(clause.to_vec());
+    }
+
+    /// Add a cube, as a list of literals to assume.
+    pub fn add_cube(&mut self, cube: &[i32]) {
+        self.cubes.push(cube.to_vec());
+    }
+
+    /// Solve the cubes.
+    pub fn solve(&mut self) -> Status {
+        let cubes = if self.cubes.is_empty() {
+            vec![vec![]]
+        } else {
+            self.cubes.clone()
+        };
+
+        let next = AtomicUsize::new(0);
+        let refuted = AtomicUsize::new(0);
+        let stop = Arc::new(AtomicBool::new(false));
+        let model: Mutex<Option<Vec<i32>>> = Mutex::new(None);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(cubes.len()) {
+                let mut solver = Solver::new();
+
+                if let Some(deadline) = self.deadline {
+                    solver.set_deadline(deadline);
+                }
+
+                if let Some(terminator) = &self.terminator {
+                    solver.set_terminator(terminator.clone());
+                }
+
+                for clause in &self.clauses {
+                    solver.add_clause(clause);
+                }
+
+                let flag = stop.clone();
+
+                solver.set_terminate(Some(Box::new(move || flag.load(Ordering::Relaxed))));
+
+                let (cubes, next, refuted, stop, model) = (&cubes, &next, &refuted, &stop, &model);
+
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+
+                    if i >= cubes.len() || stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+
+                    match solver.solve_with_assumptions(&cubes[i]) {
+                        Status::Sat => {
+                            if !stop.swap(true, Ordering::SeqCst) {
+                                *model.lock().unwrap() = Some(solver.model());
+                            }
+
+                            break;
+                        }
+
+                        // refuted without any assumption, so every cube is
+                        Status::Unsat if solver.failed().is_empty() => {
+                            refuted.store(cubes.len(), Ordering::SeqCst);
+                            stop.store(true, Ordering::SeqCst);
+                            break;
+                        }
+
+                        Status::Unsat => {
+                            refuted.fetch_add(1, Ordering::SeqCst);
+                        }
+
+                        Status::Unknown => break,
+                    }
+                });
+            }
+        });
+
+        if let Some(model) = model.into_inner().unwrap() {
+            self.model = model;
+            return Status::Sat;
+        }
+
+        self.model.clear();
+
+        if refuted.into_inner() >= cubes.len() {
+            Status::Unsat
+        } else {
+            Status::Unknown
+        }
+    }
+
+    /// The model found, as one literal per variable.
+    pub fn model(&self) -> &[i32] {
+        &self.model
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::Encoder;
+    use crate::rng::Rng;
+    use crate::testing::random_3sat;
+    use crate::words::{Bits, Word};
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(13);
+
+        for round in 0..20 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 40, 150 + round * 5);
+
+            let mut cuber = Cuber::new(&clauses);
+
+            cuber.set_depth(4);
+
+            let cubes = cuber.cubes();
+
+            assert!(cubes.len() <= 16);
+
+            let mut solver = Solver::new();
+            let mut conquer = Conquer::new(3);
+
+            for clause in &clauses {
+                solver.add_clause(clause);
+                conquer.add_clause(clause);
+            }
+
+            // satisfiable exactly if one of the cubes is
+            let status = solver.solve();
+            let sat = cubes
+                .iter()
+                .any(|cube| solver.solve_with_assumptions(cube) == Status::Sat);
+
+            assert_eq!(status == Status::Sat, sat);
+
+            for cube in &cubes {
+                conquer.add_cube(cube);
+            }
+
+            assert_eq!(conquer.solve(), status);
+
+            if status == Status::Sat {
+                let model = conquer.model();
+
+                assert!(clauses.iter().all(|c| c.iter().any(|l| model.contains(l))));
+            }
+        }
+    }
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 10);
+        let b = Word::var(&bits, 10);
+        let zero = Word::from_u64(&bits, 10, 0);
+        let target = Word::from_u64(&bits, 20, 1022117);
+        let differs = (&(&zero.concat(&a) * &zero.concat(&b)) ^ &target).any();
+
+        let mut encoder = Encoder::new(&bits, 0);
+
+        // 1022117 = 1009 * 1013, with both factors below 1024
+        let mut clauses = vec![vec![-encoder.lit(differs.ids()[0])]];
+
+        clauses.extend(encoder.take_clauses());
+
+        let mut cuber = Cuber::new(&clauses);
+
+        cuber.set_depth(6);
+
+        let mut conquer = Conquer::new(4);
+
+        for clause in &clauses {
+            conquer.add_clause(clause);
+        }
+
+        for cube in cuber.cubes() {
+            conquer.add_cube(&cube);
+        }
+
+        assert_eq!(conquer.solve(), Status::Sat);
+
+        let (x, y) = (
+            encoder.decode(&a, conquer.model()),
+            encoder.decode(&b, conquer.model()),
+        );
+
+        assert_eq!(x * y, 1022117);
+    }
+}
@@ -1,10 +1,30 @@
-use std::io::BufRead;
+use std::io::{BufRead, Write};
 use std::iter::FusedIterator;
 
-/// Iterator to produce clauses from a DIMACS formatted `BufRead` stream.
+use crate::bigint::Rational;
+
+/// Quantifier of a block of variables in a QDIMACS prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quantifier {
+    Exists,
+    Forall,
+}
+
+/// Iterator to produce clauses from a DIMACS formatted `BufRead` stream. The quantifier prefix of
+/// QDIMACS files, the CryptoMiniSat style `x` lines of XOR constraints, the projection of
+/// `c ind` or `c p show` comments and the literal weights of `c p weight` comments are collected
+/// as they are read. In iCNF files, declared by a `p inccnf` line, the `a` lines are cubes of
+/// assumptions rather than quantifier blocks, and are collected as well.
 pub struct Dimacs<R> {
     io: R,
     line: String,
+    header: Option<(usize, usize)>,
+    prefix: Vec<(Quantifier, Vec<i32>)>,
+    xors: Vec<Vec<i32>>,
+    projection: Option<Vec<i32>>,
+    weights: Vec<(i32, Rational)>,
+    incremental: bool,
+    cubes: Vec<Vec<i32>>,
 }
 
 impl<R> Dimacs<R>
@@ -16,8 +36,48 @@ where
         Dimacs {
             io,
             line: String::new(),
+            header: None,
+            prefix: Vec::new(),
+            xors: Vec::new(),
+            projection: None,
+            weights: Vec::new(),
+            incremental: false,
+            cubes: Vec::new(),
         }
     }
+
+    /// The number of variables and clauses declared by the `p cnf` line, once it has been read.
+    pub fn header(&self) -> Option<(usize, usize)> {
+        self.header
+    }
+
+    /// The blocks of quantified variables declared by the `e` and `a` lines read so far,
+    /// outermost first.
+    pub fn prefix(&self) -> &[(Quantifier, Vec<i32>)] {
+        &self.prefix
+    }
+
+    /// The XOR constraints read so far, each as literals whose exclusive or is true.
+    pub fn xors(&self) -> &[Vec<i32>] {
+        &self.xors
+    }
+
+    /// The variables to count models over declared by the `c ind` or `c p show` comments read so
+    /// far, if any.
+    pub fn projection(&self) -> Option<&[i32]> {
+        self.projection.as_deref()
+    }
+
+    /// The literal weights declared by the `c p weight` comments read so far, as exact fractions
+    /// parsed from decimals such as `0.25` or fractions such as `1/4`.
+    pub fn weights(&self) -> &[(i32, Rational)] {
+        &self.weights
+    }
+
+    /// The cubes of assumptions declared by the `a` lines of an iCNF file read so far.
+    pub fn cubes(&self) -> &[Vec<i32>] {
+        &self.cubes
+    }
 }
 
 impl<R> FusedIterator for Dimacs<R> where R: BufRead {}
@@ -44,6 +104,82 @@ where
             // remove all whitespace at beginning and end of string
             let line = self.line.trim();
 
+            if line.starts_with('p') {
+                let tokens: Vec<&str> = line.split_whitespace().collect();
+
+                if let [_, _, vars, clauses] = tokens[..] {
+                    self.header = Some((vars.parse().unwrap(), clauses.parse().unwrap()));
+                }
+
+                self.incremental = tokens[..] == ["p", "inccnf"];
+            }
+
+            let show = line
+                .strip_prefix("c ind ")
+                .or_else(|| line.strip_prefix("c p show "));
+
+            if let Some(vars) = show {
+                let vars = vars
+                    .split_whitespace()
+                    .map(|token| token.parse::<i32>().unwrap())
+                    .take_while(|var| *var != 0);
+
+                self.projection.get_or_insert_with(Vec::new).extend(vars);
+                continue;
+            }
+
+            if let Some(weight) = line.strip_prefix("c p weight ") {
+                let tokens: Vec<&str> = weight.split_whitespace().collect();
+
+                if let [literal, weight, ..] = tokens[..] {
+                    self.weights
+                        .push((literal.parse().unwrap(), weight.parse().unwrap()));
+                }
+
+                continue;
+            }
+
+            if let Some(xor) = line.strip_prefix('x') {
+                let xor = xor
+                    .split_whitespace()
+                    .map(|token| token.parse::<i32>().unwrap())
+                    .take_while(|literal| *literal != 0)
+                    .collect();
+
+                self.xors.push(xor);
+                continue;
+            }
+
+            if self.incremental && line.starts_with('a') {
+                let cube = line
+                    .split_whitespace()
+                    .skip(1)
+                    .map(|token| token.parse::<i32>().unwrap())
+                    .take_while(|literal| *literal != 0)
+                    .collect();
+
+                self.cubes.push(cube);
+                continue;
+            }
+
+            let quantifier = match line.chars().next() {
+                Some('e') => Some(Quantifier::Exists),
+                Some('a') => Some(Quantifier::Forall),
+                _ => None,
+            };
+
+            if let Some(quantifier) = quantifier {
+                let vars = line
+                    .split_whitespace()
+                    .skip(1)
+                    .map(|token| token.parse::<i32>().unwrap())
+                    .take_while(|var| *var != 0)
+                    .collect();
+
+                self.prefix.push((quantifier, vars));
+                continue;
+            }
+
             // split the line into tokens, parse them as i32, drop the trailing 0, and the collect
             // into a Vec<i32> to return
             if !(line.is_empty() || line.starts_with('c') || line.starts_with('p')) {
@@ -61,6 +197,227 @@ where
     }
 }
 
+/// A clause of a MaxSAT instance. Hard clauses have no weight.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WeightedClause {
+    pub weight: Option<u64>,
+    pub lits: Vec<i32>,
+}
+
+/// Iterator to produce weighted clauses from a WCNF formatted `BufRead` stream. Both the legacy
+/// format with a `p wcnf vars clauses top` line, where clauses weighing at least `top` are hard,
+/// and the 2022 format with `h` prefixed hard clauses are accepted.
+pub struct Wcnf<R> {
+    io: R,
+    line: String,
+    top: Option<u64>,
+}
+
+impl<R> Wcnf<R>
+where
+    R: BufRead,
+{
+    /// Create a new `Wcnf<R>` structure with the given `BufRead` stream.
+    pub fn new(io: R) -> Wcnf<R> {
+        Wcnf {
+            io,
+            line: String::new(),
+            top: None,
+        }
+    }
+
+    /// The weight declared for hard clauses by a legacy `p wcnf` line, once it has been read.
+    pub fn top(&self) -> Option<u64> {
+        self.top
+    }
+}
+
+impl<R> FusedIterator for Wcnf<R> where R: BufRead {}
+
+impl<R> Iterator for Wcnf<R>
+where
+    R: BufRead,
+{
+    type Item = WeightedClause;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+
+            let bytes = self.io.read_line(&mut self.line).unwrap();
+
+            if bytes == 0 {
+                break;
+            }
+
+            let line = self.line.trim();
+
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+
+            if line.starts_with('p') {
+                if let Some(top) = tokens.nth(4) {
+                    self.top = Some(top.parse().unwrap());
+                }
+
+                continue;
+            }
+
+            let weight = match tokens.next().unwrap() {
+                "h" => None,
+                token => Some(token.parse::<u64>().unwrap()),
+            };
+
+            let weight = match (weight, self.top) {
+                (Some(w), Some(top)) if w >= top => None,
+                (weight, _) => weight,
+            };
+
+            let lits = tokens
+                .map(|token| token.parse::<i32>().unwrap())
+                .take_while(|literal| *literal != 0)
+                .collect();
+
+            return Some(WeightedClause { weight, lits });
+        }
+
+        None
+    }
+}
+
+/// Write clauses to a stream in DIMACS format.
+pub fn write_cnf<W>(io: &mut W, num_vars: usize, clauses: &[Vec<i32>]) -> std::io::Result<()>
+where
+    W: Write,
+{
+    writeln!(io, "p cnf {} {}", num_vars, clauses.len())?;
+
+    for clause in clauses {
+        for literal in clause {
+            write!(io, "{} ", literal)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    Ok(())
+}
+
+/// Write a quantified formula to a stream in QDIMACS format.
+pub fn write_qdimacs<W>(
+    io: &mut W,
+    num_vars: usize,
+    prefix: &[(Quantifier, Vec<i32>)],
+    clauses: &[Vec<i32>],
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    writeln!(io, "p cnf {} {}", num_vars, clauses.len())?;
+
+    for (quantifier, vars) in prefix {
+        match quantifier {
+            Quantifier::Exists => write!(io, "e ")?,
+            Quantifier::Forall => write!(io, "a ")?,
+        }
+
+        for var in vars {
+            write!(io, "{} ", var)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    for clause in clauses {
+        for literal in clause {
+            write!(io, "{} ", literal)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    Ok(())
+}
+
+/// Write clauses and cubes of assumptions to a stream in iCNF format.
+pub fn write_icnf<W>(io: &mut W, clauses: &[Vec<i32>], cubes: &[Vec<i32>]) -> std::io::Result<()>
+where
+    W: Write,
+{
+    writeln!(io, "p inccnf")?;
+
+    for clause in clauses {
+        for literal in clause {
+            write!(io, "{} ", literal)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    for cube in cubes {
+        write!(io, "a ")?;
+
+        for literal in cube {
+            write!(io, "{} ", literal)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    Ok(())
+}
+
+/// Write weighted clauses to a stream in the 2022 WCNF format.
+pub fn write_wcnf<W>(io: &mut W, clauses: &[WeightedClause]) -> std::io::Result<()>
+where
+    W: Write,
+{
+    for clause in clauses {
+        match clause.weight {
+            None => write!(io, "h ")?,
+            Some(weight) => write!(io, "{} ", weight)?,
+        }
+
+        for literal in &clause.lits {
+            write!(io, "{} ", literal)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    Ok(())
+}
+
+/// Write weighted clauses to a stream in the legacy WCNF format. Hard clauses are given a weight
+/// of `top`, one more than the sum of all soft weights.
+pub fn write_wcnf_legacy<W>(
+    io: &mut W,
+    num_vars: usize,
+    clauses: &[WeightedClause],
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    let top = 1 + clauses.iter().filter_map(|c| c.weight).sum::<u64>();
+
+    writeln!(io, "p wcnf {} {} {}", num_vars, clauses.len(), top)?;
+
+    for clause in clauses {
+        write!(io, "{} ", clause.weight.unwrap_or(top))?;
+
+        for literal in &clause.lits {
+            write!(io, "{} ", literal)?;
+        }
+
+        writeln!(io, "0")?;
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -85,5 +442,179 @@ mod test {
         assert_eq!(dimacs.next(), Some(vec![-1, 5, 3, 4]));
         assert_eq!(dimacs.next(), Some(vec![-3, -4]));
         assert_eq!(dimacs.next(), None);
+        assert_eq!(dimacs.header(), Some((5, 3)));
+    }
+
+    #[test]
+    fn write_01() {
+        let clauses = vec![vec![1, -2], vec![2, 3, -1], vec![]];
+        let mut out = Vec::new();
+
+        write_cnf(&mut out, 3, &clauses).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "p cnf 3 3\n1 -2 0\n2 3 -1 0\n0\n"
+        );
+
+        let dimacs = Dimacs::new(&out[..]);
+
+        assert_eq!(dimacs.collect::<Vec<_>>(), clauses);
+    }
+
+    #[test]
+    fn icnf_01() {
+        let clauses = vec![vec![1, -2], vec![2, 3]];
+        let cubes = vec![vec![-1, 3], vec![1]];
+        let mut out = Vec::new();
+
+        write_icnf(&mut out, &clauses, &cubes).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "p inccnf\n1 -2 0\n2 3 0\na -1 3 0\na 1 0\n"
+        );
+
+        let mut dimacs = Dimacs::new(&out[..]);
+
+        assert_eq!(dimacs.by_ref().collect::<Vec<_>>(), clauses);
+        assert_eq!(dimacs.cubes(), &cubes[..]);
+        assert!(dimacs.prefix().is_empty());
+    }
+
+    #[test]
+    fn qdimacs_01() {
+        let text = "c 2QBF
+        p cnf 3 2
+        e 1 0
+        a 2 0
+        e 3 0
+        1 -2 3 0
+        -1 2 -3 0
+        ";
+
+        let mut dimacs = Dimacs::new(text.as_bytes());
+        let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+        let prefix = dimacs.prefix().to_vec();
+
+        assert_eq!(clauses, vec![vec![1, -2, 3], vec![-1, 2, -3]]);
+        assert_eq!(
+            prefix,
+            vec![
+                (Quantifier::Exists, vec![1]),
+                (Quantifier::Forall, vec![2]),
+                (Quantifier::Exists, vec![3]),
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_qdimacs(&mut out, 3, &prefix, &clauses).unwrap();
+
+        let mut dimacs = Dimacs::new(&out[..]);
+
+        assert_eq!(dimacs.by_ref().collect::<Vec<_>>(), clauses);
+        assert_eq!(dimacs.prefix(), &prefix[..]);
+    }
+
+    #[test]
+    fn xor_01() {
+        let text = "p cnf 3 2
+        1 2 0
+        x1 -2 3 0
+        x -1 3 0
+        ";
+
+        let mut dimacs = Dimacs::new(text.as_bytes());
+
+        assert_eq!(dimacs.by_ref().collect::<Vec<_>>(), vec![vec![1, 2]]);
+        assert_eq!(dimacs.xors(), &[vec![1, -2, 3], vec![-1, 3]]);
+    }
+
+    #[test]
+    fn projection_01() {
+        let text = "c ind 1 3 0
+        p cnf 3 1
+        c p show 2 0
+        1 2 3 0
+        ";
+
+        let mut dimacs = Dimacs::new(text.as_bytes());
+
+        assert_eq!(dimacs.projection(), None);
+        assert_eq!(dimacs.by_ref().count(), 1);
+        assert_eq!(dimacs.projection(), Some(&[1, 3, 2][..]));
+    }
+
+    #[test]
+    fn weights_01() {
+        let text = "p cnf 2 1
+        c p weight 1 0.25 0
+        c p weight -1 3/4 0
+        c p weight 2 2 0
+        1 2 0
+        ";
+
+        let mut dimacs = Dimacs::new(text.as_bytes());
+
+        assert_eq!(dimacs.by_ref().count(), 1);
+
+        let weights: Vec<String> = dimacs
+            .weights()
+            .iter()
+            .map(|(l, w)| format!("{} {}", l, w))
+            .collect();
+
+        assert_eq!(weights, vec!["1 1/4", "-1 3/4", "2 2"]);
+    }
+
+    #[test]
+    fn wcnf_01() {
+        let text = "c legacy format
+        p wcnf 3 4 10
+        10 1 -2 0
+        3 2 0
+        12 -1 3 0
+        1 -3 0
+        ";
+
+        let mut wcnf = Wcnf::new(text.as_bytes());
+        let clauses: Vec<WeightedClause> = wcnf.by_ref().collect();
+
+        assert_eq!(wcnf.top(), Some(10));
+        assert_eq!(
+            clauses,
+            vec![
+                WeightedClause {
+                    weight: None,
+                    lits: vec![1, -2]
+                },
+                WeightedClause {
+                    weight: Some(3),
+                    lits: vec![2]
+                },
+                WeightedClause {
+                    weight: None,
+                    lits: vec![-1, 3]
+                },
+                WeightedClause {
+                    weight: Some(1),
+                    lits: vec![-3]
+                },
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_wcnf(&mut out, &clauses).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            "h 1 -2 0\n3 2 0\nh -1 3 0\n1 -3 0\n"
+        );
+        assert_eq!(Wcnf::new(&out[..]).collect::<Vec<_>>(), clauses);
+
+        let mut out = Vec::new();
+        write_wcnf_legacy(&mut out, 3, &clauses).unwrap();
+
+        assert_eq!(Wcnf::new(&out[..]).collect::<Vec<_>>(), clauses);
     }
 }
@@ -0,0 +1,425 @@
+use std::time::Instant;
+
+use crate::drat::Drat;
+use crate::stats::{Observer, Stats};
+use crate::terminator::Terminator;
+use crate::Status;
+
+/// Resolve every clause containing `pivot` against every clause containing `-pivot`. The
+/// clauses mentioning the pivot are removed and the non-tautological resolvents take their
+/// place. The removed clauses are returned. Resolvents and deletions are logged to `proof`.
+pub fn conflicts(
+    clauses: &mut Vec<Vec<i32>>,
+    pivot: i32,
+    mut proof: Option<&mut Drat>,
+) -> Vec<Vec<i32>> {
+    let mut conflicts = Vec::new();
+
+    let pos: Vec<usize> = clauses
+        .iter()
+        .enumerate()
+        .filter(|(_, clause)| clause.contains(&pivot))
+        .map(|e| e.0)
+        .collect();
+
+    let neg: Vec<usize> = clauses
+        .iter()
+        .enumerate()
+        .filter(|(_, clause)| clause.contains(&-pivot))
+        .map(|e| e.0)
+        .collect();
+
+    for p in &pos {
+        for n in &neg {
+            if p == n {
+                continue;
+            }
+
+            let mut conflict: Vec<i32> = clauses[*p]
+                .iter()
+                .filter(|l| **l != pivot)
+                .chain(clauses[*n].iter().filter(|l| **l != -pivot))
+                .copied()
+                .collect();
+
+            conflict.sort_unstable();
+            conflict.dedup();
+
+            // a resolvent containing both polarities of a variable is satisfied by every
+            // assignment and only bloats the clause set
+            if conflict.iter().any(|l| conflict.contains(&-l)) {
+                continue;
+            }
+
+            if let Some(proof) = proof.as_mut() {
+                proof.add(&conflict);
+            }
+
+            conflicts.push(conflict);
+        }
+    }
+
+    let mut indexes: Vec<usize> = pos.iter().chain(neg.iter()).copied().collect();
+
+    indexes.sort_unstable();
+    indexes.dedup();
+
+    let mut removed = Vec::with_capacity(indexes.len());
+
+    for index in indexes.iter().rev() {
+        let clause = clauses.swap_remove(*index);
+
+        if let Some(proof) = proof.as_mut() {
+            proof.delete(&clause);
+        }
+
+        removed.push(clause);
+    }
+
+    clauses.extend(conflicts);
+
+    for clause in clauses.iter_mut() {
+        clause.sort_unstable();
+        clause.dedup();
+    }
+
+    clauses.sort_unstable();
+    clauses.dedup();
+
+    removed
+}
+
+/// Davis–Putnam decision procedure. Variables are eliminated one at a time by resolution until
+/// either the empty clause is derived or no clauses remain.
+pub struct DavisPutnam {
+    clauses: Vec<Vec<i32>>,
+    eliminated: Vec<(i32, Vec<Vec<i32>>)>,
+    num_vars: usize,
+    budget: usize,
+    terminator: Option<Terminator>,
+    proof: Option<Drat>,
+    observer: Option<Box<dyn Observer>>,
+    stats: Stats,
+}
+
+impl DavisPutnam {
+    /// Create a new procedure over the given clauses. Literals within each clause are sorted and
+    /// deduplicated, and tautologies are dropped.
+    pub fn new(clauses: Vec<Vec<i32>>) -> DavisPutnam {
+        let num_vars = clauses
+            .iter()
+            .flatten()
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+
+        let mut clauses: Vec<Vec<i32>> = clauses
+            .into_iter()
+            .map(|mut clause| {
+                clause.sort_unstable();
+                clause.dedup();
+                clause
+            })
+            .filter(|clause| !clause.iter().any(|l| clause.contains(&-l)))
+            .collect();
+
+        clauses.sort_unstable();
+        clauses.dedup();
+
+        DavisPutnam {
+            clauses,
+            eliminated: Vec::new(),
+            num_vars,
+            budget: usize::MAX,
+            terminator: None,
+            proof: None,
+            observer: None,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Limit the number of clauses the procedure may hold. Once exceeded, the procedure gives up
+    /// and reports `Status::Unknown`.
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops. Elimination has no
+    /// conflicts, so only the conflict limit does not apply.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Pass each elimination to the observer.
+    pub fn set_observer(&mut self, observer: Option<Box<dyn Observer>>) {
+        self.observer = observer;
+    }
+
+    /// The number of eliminations and the time they took.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Log resolvents and deleted clauses to a DRAT proof from now on.
+    pub fn set_proof(&mut self, proof: Drat) {
+        self.proof = Some(proof);
+    }
+
+    /// Stop logging and return the proof, e.g. to continue it in a `Solver`.
+    pub fn take_proof(&mut self) -> Option<Drat> {
+        self.proof.take()
+    }
+
+    /// The current clause set.
+    pub fn clauses(&self) -> &[Vec<i32>] {
+        &self.clauses
+    }
+
+    /// Assign the variables up to `num_vars` in models as well, such as those occurring only in
+    /// constraints kept outside the clause set.
+    pub fn set_num_vars(&mut self, num_vars: usize) {
+        self.num_vars = self.num_vars.max(num_vars);
+    }
+
+    /// The largest variable of the original clause set.
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// The number of variables eliminated so far.
+    pub fn eliminated(&self) -> usize {
+        self.eliminated.len()
+    }
+
+    /// Choose the next variable to eliminate. The pivot is the literal whose elimination adds the
+    /// fewest clauses in the worst case, i.e. minimizing `pos * neg - pos - neg`.
+    pub fn pivot(&self) -> Option<i32> {
+        let mut pos = vec![0usize; self.num_vars + 1];
+        let mut neg = vec![0usize; self.num_vars + 1];
+
+        for literal in self.clauses.iter().flatten() {
+            if *literal > 0 {
+                pos[*literal as usize] += 1;
+            } else {
+                neg[literal.unsigned_abs() as usize] += 1;
+            }
+        }
+
+        (1..=self.num_vars)
+            .filter(|v| pos[*v] + neg[*v] > 0)
+            .min_by_key(|v| (pos[*v] * neg[*v]) as isize - (pos[*v] + neg[*v]) as isize)
+            .map(|v| if pos[v] > 0 { v as i32 } else { -(v as i32) })
+    }
+
+    /// The number of clauses the elimination of `pivot` adds in the worst case. Negative values
+    /// mean the clause set shrinks.
+    pub fn growth(&self, pivot: i32) -> isize {
+        let pos = self.clauses.iter().filter(|c| c.contains(&pivot)).count() as isize;
+        let neg = self.clauses.iter().filter(|c| c.contains(&-pivot)).count() as isize;
+
+        pos * neg - pos - neg
+    }
+
+    /// The conclusion reached on the current clause set, if any.
+    pub fn status(&self) -> Option<Status> {
+        if self.clauses.iter().any(|clause| clause.is_empty()) {
+            return Some(Status::Unsat);
+        }
+
+        if self.clauses.is_empty() {
+            return Some(Status::Sat);
+        }
+
+        if self.clauses.len() > self.budget {
+            return Some(Status::Unknown);
+        }
+
+        if let Some(terminator) = &self.terminator {
+            if terminator.should_stop(0) || terminator.out_of_memory() {
+                return Some(Status::Unknown);
+            }
+        }
+
+        None
+    }
+
+    /// Eliminate the variable of `pivot` by resolution.
+    pub fn eliminate(&mut self, pivot: i32) {
+        let start = Instant::now();
+        let before = self.clauses.len();
+        let removed = conflicts(&mut self.clauses, pivot, self.proof.as_mut());
+
+        self.stats.eliminations += 1;
+        self.stats.elimination_time += start.elapsed();
+
+        if let Some(observer) = &mut self.observer {
+            observer.eliminated(pivot, before, self.clauses.len());
+        }
+
+        self.eliminated.push((
+            pivot,
+            removed
+                .into_iter()
+                .filter(|clause| clause.contains(&pivot))
+                .collect(),
+        ));
+    }
+
+    /// Perform a single elimination step. Returns `None` while the procedure has not yet reached
+    /// a conclusion.
+    pub fn step(&mut self) -> Option<Status> {
+        if let Some(status) = self.status() {
+            return Some(status);
+        }
+
+        let pivot = self.pivot()?;
+
+        self.eliminate(pivot);
+
+        None
+    }
+
+    /// Run the procedure to completion.
+    pub fn solve(&mut self) -> Status {
+        loop {
+            if let Some(status) = self.step() {
+                return status;
+            }
+        }
+    }
+
+    /// Extend a model of the current clause set to a model of the original clause set by
+    /// assigning the eliminated variables in reverse order of elimination. Variables not
+    /// mentioned anywhere are assigned false.
+    pub fn reconstruct(&self, model: &[i32]) -> Vec<i32> {
+        let mut values = vec![false; self.num_vars + 1];
+
+        for literal in model {
+            let var = literal.unsigned_abs() as usize;
+
+            if var < values.len() {
+                values[var] = *literal > 0;
+            }
+        }
+
+        let is_true = |values: &[bool], l: i32| values[l.unsigned_abs() as usize] == (l > 0);
+
+        for (pivot, clauses) in self.eliminated.iter().rev() {
+            let var = pivot.unsigned_abs() as usize;
+
+            values[var] = *pivot < 0;
+
+            let unsatisfied = clauses.iter().any(|clause| {
+                !clause
+                    .iter()
+                    .filter(|l| *l != pivot)
+                    .any(|l| is_true(&values, *l))
+            });
+
+            if unsatisfied {
+                values[var] = *pivot > 0;
+            }
+        }
+
+        (1..=self.num_vars)
+            .map(|v| if values[v] { v as i32 } else { -(v as i32) })
+            .collect()
+    }
+
+    /// A model of the original clause set, valid once `solve` has returned `Status::Sat`.
+    pub fn model(&self) -> Vec<i32> {
+        self.reconstruct(&[])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::solver::Solver;
+
+    fn satisfies(clauses: &[Vec<i32>], model: &[i32]) -> bool {
+        clauses
+            .iter()
+            .all(|clause| clause.iter().any(|l| model.contains(l)))
+    }
+
+    #[test]
+    fn conflicts_01() {
+        let mut clauses = vec![vec![1, 2], vec![-1, 3], vec![-1, -2], vec![2, 3]];
+
+        let removed = conflicts(&mut clauses, 1, None);
+
+        assert_eq!(removed.len(), 3);
+        assert_eq!(clauses, vec![vec![2, 3]]);
+    }
+
+    #[test]
+    fn sat_01() {
+        let clauses = vec![
+            vec![1, -5, 4],
+            vec![-1, 5, 3, 4],
+            vec![-3, -4],
+            vec![2, 3],
+            vec![-2, -3, 5],
+        ];
+
+        let mut dp = DavisPutnam::new(clauses.clone());
+
+        assert_eq!(dp.solve(), Status::Sat);
+        assert!(satisfies(&clauses, &dp.model()));
+    }
+
+    #[test]
+    fn unsat_01() {
+        let clauses = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+
+        let mut dp = DavisPutnam::new(clauses);
+
+        assert_eq!(dp.solve(), Status::Unsat);
+    }
+
+    #[test]
+    fn budget_01() {
+        let clauses = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+
+        let mut dp = DavisPutnam::new(clauses);
+        dp.set_budget(3);
+
+        assert_eq!(dp.solve(), Status::Unknown);
+    }
+
+    #[test]
+    fn terminator_01() {
+        let clauses = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+        let terminator = Terminator::new();
+
+        let mut dp = DavisPutnam::new(clauses);
+        dp.set_terminator(terminator.clone());
+
+        terminator.terminate();
+
+        assert_eq!(dp.solve(), Status::Unknown);
+        assert_eq!(dp.eliminated(), 0);
+    }
+
+    #[test]
+    fn xor_01() {
+        let mut dp = DavisPutnam::new(vec![vec![1, 2, 3]]);
+        dp.set_num_vars(5);
+
+        let mut solver = Solver::new();
+
+        for clause in dp.clauses() {
+            solver.add_clause(clause);
+        }
+
+        solver.add_xor(&[4, 5]);
+
+        assert_eq!(solver.solve(), Status::Sat);
+
+        let model = dp.reconstruct(&solver.model());
+
+        assert_eq!(model.len(), 5);
+        assert!(model.contains(&4) != model.contains(&5));
+    }
+}
@@ -0,0 +1,121 @@
+use std::io::{self, Write};
+
+/// Writer for clausal proofs in the DRAT format, either as text or in the compact binary
+/// encoding. Write errors are remembered and reported by `finish`, so that proof logging does
+/// not interrupt the procedure producing the proof.
+pub struct Drat {
+    io: Box<dyn Write + Send>,
+    binary: bool,
+    error: Option<io::Error>,
+}
+
+impl Drat {
+    /// Create a new proof writing to the given stream.
+    pub fn new<W>(io: W, binary: bool) -> Drat
+    where
+        W: Write + Send + 'static,
+    {
+        Drat {
+            io: Box::new(io),
+            binary,
+            error: None,
+        }
+    }
+
+    /// Record the addition of a clause.
+    pub fn add(&mut self, clause: &[i32]) {
+        self.line(b'a', clause);
+    }
+
+    /// Record the deletion of a clause.
+    pub fn delete(&mut self, clause: &[i32]) {
+        self.line(b'd', clause);
+    }
+
+    /// Flush the proof and report the first write error, if any.
+    pub fn finish(mut self) -> io::Result<()> {
+        if let Some(error) = self.error.take() {
+            return Err(error);
+        }
+
+        self.io.flush()
+    }
+
+    fn line(&mut self, kind: u8, clause: &[i32]) {
+        if self.error.is_some() {
+            return;
+        }
+
+        let result = if self.binary {
+            write_binary(&mut self.io, kind, clause)
+        } else {
+            write_text(&mut self.io, kind, clause)
+        };
+
+        if let Err(error) = result {
+            self.error = Some(error);
+        }
+    }
+}
+
+fn write_text<W: Write>(io: &mut W, kind: u8, clause: &[i32]) -> io::Result<()> {
+    if kind == b'd' {
+        write!(io, "d ")?;
+    }
+
+    for literal in clause {
+        write!(io, "{} ", literal)?;
+    }
+
+    writeln!(io, "0")
+}
+
+/// Binary DRAT: a kind byte followed by each literal `l` mapped to `2 * |l| + (l < 0)` and
+/// written as a little-endian base-128 varint, terminated by a zero byte.
+fn write_binary<W: Write>(io: &mut W, kind: u8, clause: &[i32]) -> io::Result<()> {
+    let mut bytes = vec![kind];
+
+    for literal in clause {
+        let mut n = 2 * literal.unsigned_abs() + (*literal < 0) as u32;
+
+        while n > 0x7f {
+            bytes.push((n & 0x7f) as u8 | 0x80);
+            n >>= 7;
+        }
+
+        bytes.push(n as u8);
+    }
+
+    bytes.push(0);
+
+    io.write_all(&bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn text_01() {
+        let mut out = Vec::new();
+
+        write_text(&mut out, b'a', &[1, -2]).unwrap();
+        write_text(&mut out, b'd', &[3]).unwrap();
+        write_text(&mut out, b'a', &[]).unwrap();
+
+        assert_eq!(String::from_utf8(out).unwrap(), "1 -2 0\nd 3 0\n0\n");
+    }
+
+    #[test]
+    fn binary_01() {
+        let mut out = Vec::new();
+
+        write_binary(&mut out, b'a', &[1, -63, 64]).unwrap();
+        write_binary(&mut out, b'd', &[-8193]).unwrap();
+
+        assert_eq!(
+            out,
+            vec![0x61, 0x02, 0x7f, 0x80, 0x01, 0x00, 0x64, 0x83, 0x80, 0x01, 0x00]
+        );
+    }
+}
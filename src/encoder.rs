@@ -0,0 +1,357 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::solver::Solver;
+use crate::words::{Bit, Bits, Word};
+
+/// Tseitin encoding of the expressions of a `Bits` container into clauses. Each encoded
+/// expression is given a variable numbered after the variables already in use, and the clauses
+/// defining it are queued until taken with `take_clauses`. Encoded expressions are kept alive so
+/// that `Bits` does not reuse their ids for different expressions.
+///
+/// With native XOR constraints enabled, a tree of exclusive ors is flattened into a single XOR
+/// constraint over its leaves, queued until taken with `take_xors`, rather than a chain of
+/// clauses growing with each level.
+pub struct Encoder {
+    bits: Bits,
+    lits: HashMap<u32, i32>,
+    truth: Option<i32>,
+    num_vars: usize,
+    clauses: Vec<Vec<i32>>,
+    native_xor: bool,
+    xors: Vec<Vec<i32>>,
+}
+
+impl Encoder {
+    /// Create an encoder for the given container, numbering new variables after `num_vars`.
+    pub fn new(bits: &Bits, num_vars: usize) -> Encoder {
+        Encoder {
+            bits: bits.clone(),
+            lits: HashMap::new(),
+            truth: None,
+            num_vars,
+            clauses: Vec::new(),
+            native_xor: false,
+            xors: Vec::new(),
+        }
+    }
+
+    /// Encode exclusive ors as XOR constraints, for a solver supporting them.
+    pub fn set_native_xor(&mut self, native: bool) {
+        self.native_xor = native;
+    }
+
+    /// The number of variables in use, including those created by the encoder.
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// Create a variable unrelated to any expression.
+    pub fn fresh(&mut self) -> i32 {
+        self.num_vars += 1;
+        self.num_vars as i32
+    }
+
+    /// The literal equivalent to an expression, encoding it and its subexpressions if needed.
+    pub fn lit(&mut self, id: u32) -> i32 {
+        let mut pending = vec![id];
+
+        while let Some(&id) = pending.last() {
+            if self.lits.contains_key(&id) {
+                pending.pop();
+                continue;
+            }
+
+            let bit = self.bits.get(id);
+
+            let children = match bit {
+                Bit::Xor(..) if self.native_xor => self.leaves(id),
+                Bit::And(a, b) | Bit::Or(a, b) | Bit::Xor(a, b) => vec![a, b],
+                Bit::Not(a) => vec![a],
+                _ => vec![],
+            };
+
+            let missing: Vec<u32> = children
+                .iter()
+                .filter(|c| !self.lits.contains_key(c))
+                .copied()
+                .collect();
+
+            if !missing.is_empty() {
+                pending.extend(missing);
+                continue;
+            }
+
+            pending.pop();
+
+            let l = match bit {
+                Bit::Unused => panic!("expression {} is not in use", id),
+
+                Bit::Var => self.fresh(),
+
+                Bit::Val(v) => {
+                    let t = self.truth();
+
+                    if v {
+                        t
+                    } else {
+                        -t
+                    }
+                }
+
+                Bit::Not(a) => -self.lits[&a],
+
+                Bit::And(a, b) => {
+                    let (a, b, x) = (self.lits[&a], self.lits[&b], self.fresh());
+
+                    self.clauses.push(vec![-x, a]);
+                    self.clauses.push(vec![-x, b]);
+                    self.clauses.push(vec![x, -a, -b]);
+
+                    x
+                }
+
+                Bit::Or(a, b) => {
+                    let (a, b, x) = (self.lits[&a], self.lits[&b], self.fresh());
+
+                    self.clauses.push(vec![x, -a]);
+                    self.clauses.push(vec![x, -b]);
+                    self.clauses.push(vec![-x, a, b]);
+
+                    x
+                }
+
+                Bit::Xor(..) if self.native_xor => {
+                    let x = self.fresh();
+                    let mut xor = vec![-x];
+
+                    xor.extend(children.iter().map(|c| self.lits[c]));
+                    self.xors.push(xor);
+
+                    x
+                }
+
+                Bit::Xor(a, b) => {
+                    let (a, b, x) = (self.lits[&a], self.lits[&b], self.fresh());
+
+                    self.clauses.push(vec![-x, a, b]);
+                    self.clauses.push(vec![-x, -a, -b]);
+                    self.clauses.push(vec![x, -a, b]);
+                    self.clauses.push(vec![x, a, -b]);
+
+                    x
+                }
+            };
+
+            self.bits.incr(id);
+            self.lits.insert(id, l);
+        }
+
+        self.lits[&id]
+    }
+
+    /// The literals equivalent to the bits of a word, least significant first.
+    pub fn word(&mut self, word: &Word) -> Vec<i32> {
+        assert!(self.bits.ptr_eq(word.bits()));
+
+        word.ids().iter().map(|id| self.lit(*id)).collect()
+    }
+
+    /// The value of an encoded word in a model given as one literal per variable, as returned
+    /// by `Solver::model`.
+    pub fn decode(&mut self, word: &Word, model: &[i32]) -> u64 {
+        assert!(word.width() <= 64);
+
+        self.word(word)
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| model.get(l.unsigned_abs() as usize - 1) == Some(*l))
+            .fold(0, |n, (i, _)| n | 1 << i)
+    }
+
+    /// Take the clauses defining the expressions encoded since the last call.
+    pub fn take_clauses(&mut self) -> Vec<Vec<i32>> {
+        std::mem::take(&mut self.clauses)
+    }
+
+    /// Take the XOR constraints defining the expressions encoded since the last call, each as
+    /// literals whose exclusive or is true.
+    pub fn take_xors(&mut self) -> Vec<Vec<i32>> {
+        std::mem::take(&mut self.xors)
+    }
+
+    /// Add the clauses and XOR constraints defining the expressions encoded since the last call
+    /// to a solver, outside of its scopes, so that they remain valid for later queries.
+    pub fn add_to(&mut self, solver: &mut Solver) {
+        for clause in self.take_clauses() {
+            solver.add_definition(&clause);
+        }
+
+        for xor in self.take_xors() {
+            solver.add_xor(&xor);
+        }
+    }
+
+    /// The operands of the tree of exclusive ors rooted at an expression, descending through
+    /// those not encoded yet. Operands occurring twice cancel.
+    fn leaves(&self, id: u32) -> Vec<u32> {
+        let mut leaves = BTreeSet::new();
+        let mut pending = vec![id];
+
+        while let Some(id) = pending.pop() {
+            match self.bits.get(id) {
+                Bit::Xor(a, b) if !self.lits.contains_key(&id) => pending.extend([a, b]),
+
+                _ => {
+                    if !leaves.remove(&id) {
+                        leaves.insert(id);
+                    }
+                }
+            }
+        }
+
+        leaves.into_iter().collect()
+    }
+
+    /// A literal fixed to true, shared by all constants.
+    fn truth(&mut self) -> i32 {
+        match self.truth {
+            Some(t) => t,
+            None => {
+                let t = self.fresh();
+
+                self.clauses.push(vec![t]);
+                self.truth = Some(t);
+
+                t
+            }
+        }
+    }
+}
+
+impl Drop for Encoder {
+    fn drop(&mut self) {
+        for id in self.lits.keys() {
+            self.bits.decr(*id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Status;
+
+    #[test]
+    fn mul_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 8);
+        let b = Word::var(&bits, 8);
+        let one = Word::from_u64(&bits, 8, 1);
+        let target = Word::from_u64(&bits, 8, 143);
+
+        let differs = (&(&a * &b) ^ &target).any();
+        let a_one = (&a ^ &one).any();
+        let b_one = (&b ^ &one).any();
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut solver = Solver::new();
+
+        // a * b = 143 modulo 256 with neither factor equal to one
+        solver.add_clause(&[-encoder.lit(differs.ids()[0])]);
+        solver.add_clause(&[encoder.lit(a_one.ids()[0])]);
+        solver.add_clause(&[encoder.lit(b_one.ids()[0])]);
+
+        for clause in encoder.take_clauses() {
+            solver.add_clause(&clause);
+        }
+
+        assert_eq!(solver.solve(), Status::Sat);
+
+        let model = solver.model();
+        let x = encoder.decode(&a, &model);
+        let y = encoder.decode(&b, &model);
+
+        assert_eq!(x * y % 256, 143);
+        assert!(x != 1 && y != 1);
+    }
+
+    #[test]
+    fn parity_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 16);
+        let parity = a.parity();
+        let low = Word::from_u64(&bits, 16, 0xff);
+        let masked = (&a & &low).parity();
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut solver = Solver::new();
+
+        encoder.set_native_xor(true);
+
+        // odd parity overall, even parity of the low byte, and a fixed low byte
+        solver.add_clause(&[encoder.lit(parity.ids()[0])]);
+        solver.add_clause(&[-encoder.lit(masked.ids()[0])]);
+
+        for (i, l) in encoder.word(&a).iter().enumerate().take(8) {
+            solver.add_clause(&[if 0x96 >> i & 1 == 1 { *l } else { -l }]);
+        }
+
+        for clause in encoder.take_clauses() {
+            solver.add_clause(&clause);
+        }
+
+        let xors = encoder.take_xors();
+
+        assert_eq!(xors.len(), 2);
+
+        for xor in &xors {
+            solver.add_xor(xor);
+        }
+
+        assert_eq!(solver.solve(), Status::Sat);
+
+        let x = encoder.decode(&a, &solver.model());
+
+        assert_eq!(x & 0xff, 0x96);
+        assert_eq!(x.count_ones() % 2, 1);
+    }
+
+    #[test]
+    fn incremental_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 8);
+        let b = Word::var(&bits, 8);
+        let product = &a * &b;
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut solver = Solver::new();
+
+        encoder.word(&product);
+        encoder.add_to(&mut solver);
+
+        for expected in [143, 15, 221] {
+            let target = Word::from_u64(&bits, 8, expected);
+            let differs = (&product ^ &target).any();
+
+            // only the comparison is new, the product was encoded before the first query
+            let l = encoder.lit(differs.ids()[0]);
+            let activation = encoder.fresh();
+
+            encoder.add_to(&mut solver);
+
+            solver.push(activation);
+            solver.add_clause(&[-l]);
+
+            assert_eq!(solver.solve(), Status::Sat);
+
+            let model = solver.model();
+
+            assert_eq!(
+                encoder.decode(&a, &model) * encoder.decode(&b, &model) % 256,
+                expected
+            );
+
+            solver.pop();
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::iter::FusedIterator;
+
+use crate::encoder::Encoder;
+use crate::solver::Solver;
+use crate::words::Word;
+use crate::Status;
+
+/// Iterator over the models of a solver's clauses projected onto a set of variables, each as one
+/// literal per variable. Models are found lazily, one call to the solver each, and every model
+/// is excluded from the following calls by a blocking clause over the projection, so that no
+/// projected model is produced twice. The blocking clauses stay in the solver.
+pub struct Models<'a> {
+    solver: &'a mut Solver,
+    vars: Vec<i32>,
+    done: bool,
+}
+
+impl<'a> Models<'a> {
+    /// Enumerate the models of the solver's clauses projected onto `vars`.
+    pub fn new(solver: &'a mut Solver, vars: &[i32]) -> Models<'a> {
+        Models {
+            solver,
+            vars: vars.to_vec(),
+            done: false,
+        }
+    }
+}
+
+impl FusedIterator for Models<'_> {}
+
+impl Iterator for Models<'_> {
+    type Item = Vec<i32>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        if self.solver.solve() != Status::Sat {
+            self.done = true;
+            return None;
+        }
+
+        let model: Vec<i32> = self
+            .vars
+            .iter()
+            .map(|v| {
+                if self.solver.value(*v) == Some(true) {
+                    *v
+                } else {
+                    -v
+                }
+            })
+            .collect();
+
+        let blocking: Vec<i32> = model.iter().map(|l| -l).collect();
+
+        // an empty projection has a single model, and blocking it leaves the empty clause
+        self.solver.add_clause(&blocking);
+        self.done = blocking.is_empty();
+
+        Some(model)
+    }
+}
+
+/// Iterator over the distinct values of a set of words in the models of a solver's clauses,
+/// built on `Models` projected onto the bits of the words.
+pub struct WordModels<'a> {
+    models: Models<'a>,
+    lits: Vec<Vec<i32>>,
+}
+
+impl<'a> WordModels<'a> {
+    /// Enumerate the values of `words`, encoding them as needed. The words must be at most 64
+    /// bits wide.
+    pub fn new(encoder: &mut Encoder, solver: &'a mut Solver, words: &[&Word]) -> WordModels<'a> {
+        assert!(words.iter().all(|w| w.width() <= 64));
+
+        let lits: Vec<Vec<i32>> = words.iter().map(|w| encoder.word(w)).collect();
+
+        encoder.add_to(solver);
+
+        let mut vars: Vec<i32> = lits.iter().flatten().map(|l| l.abs()).collect();
+
+        vars.sort_unstable();
+        vars.dedup();
+
+        WordModels {
+            models: Models::new(solver, &vars),
+            lits,
+        }
+    }
+}
+
+impl FusedIterator for WordModels<'_> {}
+
+impl Iterator for WordModels<'_> {
+    type Item = Vec<u64>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let model = self.models.next()?;
+        let values: HashMap<i32, bool> = model.iter().map(|l| (l.abs(), *l > 0)).collect();
+
+        Some(
+            self.lits
+                .iter()
+                .map(|word| {
+                    word.iter()
+                        .enumerate()
+                        .filter(|(_, l)| values[&l.abs()] == (**l > 0))
+                        .fold(0, |n, (i, _)| n | 1 << i)
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::words::Bits;
+
+    #[test]
+    fn simple_01() {
+        let mut solver = Solver::new();
+
+        // exactly one of 1, 2 and 3, with 4 free
+        for clause in &[
+            vec![1, 2, 3],
+            vec![-1, -2],
+            vec![-1, -3],
+            vec![-2, -3],
+            vec![4, -4],
+        ] {
+            solver.add_clause(clause);
+        }
+
+        let mut models: Vec<Vec<i32>> = Models::new(&mut solver, &[1, 2, 3]).collect();
+
+        models.sort_unstable();
+
+        assert_eq!(
+            models,
+            vec![vec![-1, -2, 3], vec![-1, 2, -3], vec![1, -2, -3]]
+        );
+
+        let mut solver = Solver::new();
+
+        solver.add_clause(&[1, 2]);
+
+        assert_eq!(Models::new(&mut solver, &[]).count(), 1);
+    }
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 4);
+        let b = Word::var(&bits, 4);
+        let target = Word::from_u64(&bits, 4, 10);
+        let differs = (&(&a + &b) ^ &target).any();
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut solver = Solver::new();
+
+        solver.add_clause(&[-encoder.lit(differs.ids()[0])]);
+
+        let first: Vec<Vec<u64>> = WordModels::new(&mut encoder, &mut solver, &[&a])
+            .take(3)
+            .collect();
+
+        assert_eq!(first.len(), 3);
+
+        // the models found are blocked, so the rest of the values of a follow
+        let rest: Vec<Vec<u64>> = WordModels::new(&mut encoder, &mut solver, &[&a, &b]).collect();
+
+        assert_eq!(rest.len(), 13);
+
+        for pair in &rest {
+            assert_eq!((pair[0] + pair[1]) % 16, 10);
+            assert!(!first.contains(&vec![pair[0]]));
+        }
+    }
+}
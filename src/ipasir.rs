@@ -0,0 +1,277 @@
+//! The IPASIR interface for incremental SAT solvers, so that programs written against the
+//! standard `ipasir.h` header can link against rsat as a shared or static library.
+
+use std::collections::HashSet;
+use std::os::raw::{c_char, c_int, c_void};
+
+use crate::solver::{Learn, Solver, Terminate};
+use crate::Status;
+
+struct Ipasir {
+    solver: Solver,
+    clause: Vec<i32>,
+    assumptions: Vec<i32>,
+    failed: HashSet<i32>,
+    status: Status,
+}
+
+/// Opaque data of a callback, passed back as is to the callback.
+struct Data(*mut c_void);
+
+// the callbacks are only called from the thread solving
+unsafe impl Send for Data {}
+
+const SIGNATURE: &str = concat!("rsat ", env!("CARGO_PKG_VERSION"), "\0");
+
+/// The name and version of the solver.
+#[no_mangle]
+pub extern "C" fn ipasir_signature() -> *const c_char {
+    SIGNATURE.as_ptr() as *const c_char
+}
+
+/// Create a new solver, to be released with `ipasir_release`.
+#[no_mangle]
+pub extern "C" fn ipasir_init() -> *mut c_void {
+    let ipasir = Ipasir {
+        solver: Solver::new(),
+        clause: Vec::new(),
+        assumptions: Vec::new(),
+        failed: HashSet::new(),
+        status: Status::Unknown,
+    };
+
+    Box::into_raw(Box::new(ipasir)) as *mut c_void
+}
+
+/// Release a solver.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_release(solver: *mut c_void) {
+    drop(Box::from_raw(solver as *mut Ipasir));
+}
+
+/// Add a literal to the clause being built, or add the clause when the literal is zero.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_add(solver: *mut c_void, lit_or_zero: i32) {
+    let ipasir = &mut *(solver as *mut Ipasir);
+
+    if lit_or_zero == 0 {
+        ipasir.solver.add_clause(&ipasir.clause);
+        ipasir.clause.clear();
+    } else {
+        ipasir.clause.push(lit_or_zero);
+    }
+}
+
+/// Assume a literal for the next call to `ipasir_solve`. Zero is ignored.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_assume(solver: *mut c_void, lit: i32) {
+    let ipasir = &mut *(solver as *mut Ipasir);
+
+    if lit != 0 {
+        ipasir.assumptions.push(lit);
+    }
+}
+
+/// Solve under the assumptions made since the last call. Returns 10 if satisfiable, 20 if
+/// unsatisfiable and 0 if interrupted.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_solve(solver: *mut c_void) -> c_int {
+    let ipasir = &mut *(solver as *mut Ipasir);
+    let assumptions = std::mem::take(&mut ipasir.assumptions);
+
+    ipasir.status = ipasir.solver.solve_with_assumptions(&assumptions);
+    ipasir.failed = ipasir.solver.failed().iter().copied().collect();
+
+    match ipasir.status {
+        Status::Sat => 10,
+        Status::Unsat => 20,
+        Status::Unknown => 0,
+    }
+}
+
+/// The literal if it is true in the model found by the last call to `ipasir_solve`, its
+/// complement if false, or zero if the variable is irrelevant or the literal is zero.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`, and the last call to `ipasir_solve` must have returned
+/// 10.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_val(solver: *mut c_void, lit: i32) -> i32 {
+    let ipasir = &*(solver as *mut Ipasir);
+
+    // a panic must not unwind into the caller
+    if lit == 0 {
+        return 0;
+    }
+
+    match ipasir.solver.value(lit) {
+        Some(true) => lit,
+        Some(false) => -lit,
+        None => 0,
+    }
+}
+
+/// 1 if the assumption `lit` was used to prove unsatisfiability in the last call to
+/// `ipasir_solve`, and 0 otherwise.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`, and the last call to `ipasir_solve` must have returned
+/// 20.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_failed(solver: *mut c_void, lit: i32) -> c_int {
+    let ipasir = &*(solver as *mut Ipasir);
+
+    ipasir.failed.contains(&lit) as c_int
+}
+
+/// Poll `terminate` while solving, and give up as soon as it returns a nonzero value. A null
+/// callback removes the previous one.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`, and `terminate` must be safe to call with `data`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_terminate(
+    solver: *mut c_void,
+    data: *mut c_void,
+    terminate: Option<extern "C" fn(data: *mut c_void) -> c_int>,
+) {
+    let ipasir = &mut *(solver as *mut Ipasir);
+    let data = Data(data);
+
+    ipasir.solver.set_terminate(
+        terminate.map(|terminate| Box::new(move || terminate(data.0) != 0) as Terminate),
+    );
+}
+
+/// Pass each learnt clause of at most `max_length` literals to `learn`, as a zero terminated
+/// array valid for the duration of the call. A null callback removes the previous one.
+///
+/// # Safety
+///
+/// `solver` must come from `ipasir_init`, and `learn` must be safe to call with `data`.
+#[no_mangle]
+pub unsafe extern "C" fn ipasir_set_learn(
+    solver: *mut c_void,
+    data: *mut c_void,
+    max_length: c_int,
+    learn: Option<extern "C" fn(data: *mut c_void, clause: *mut i32)>,
+) {
+    let ipasir = &mut *(solver as *mut Ipasir);
+    let data = Data(data);
+
+    let learn = learn.map(|learn| {
+        Box::new(move |clause: &[i32]| {
+            let mut clause = clause.to_vec();
+
+            clause.push(0);
+            learn(data.0, clause.as_mut_ptr());
+        }) as Learn
+    });
+
+    ipasir.solver.set_learn(max_length.max(0) as usize, learn);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    extern "C" fn count(data: *mut c_void, clause: *mut i32) {
+        unsafe {
+            let mut len = 0;
+
+            while *clause.add(len) != 0 {
+                len += 1;
+            }
+
+            assert!(len <= 2);
+            *(data as *mut usize) += 1;
+        }
+    }
+
+    extern "C" fn stop(_: *mut c_void) -> c_int {
+        1
+    }
+
+    #[test]
+    fn simple_01() {
+        unsafe {
+            let solver = ipasir_init();
+            let mut learnt = 0usize;
+
+            ipasir_set_learn(
+                solver,
+                &mut learnt as *mut usize as *mut c_void,
+                2,
+                Some(count),
+            );
+
+            for clause in &[[1, 2, 0], [-1, 3, 0], [-2, 3, 0]] {
+                for l in clause {
+                    ipasir_add(solver, *l);
+                }
+            }
+
+            ipasir_assume(solver, -3);
+            ipasir_assume(solver, 0);
+
+            assert_eq!(ipasir_solve(solver), 20);
+            assert_eq!(ipasir_failed(solver, -3), 1);
+
+            assert_eq!(ipasir_solve(solver), 10);
+            assert_eq!(ipasir_val(solver, 3), 3);
+            assert_eq!(ipasir_val(solver, -3), 3);
+            assert_eq!(ipasir_val(solver, 0), 0);
+
+            // pigeonhole with 5 pigeons and 4 holes takes more than one conflict
+            let p = |i: i32, j: i32| 10 + i * 4 + j;
+
+            for i in 0..5 {
+                for j in 0..4 {
+                    ipasir_add(solver, p(i, j));
+                }
+
+                ipasir_add(solver, 0);
+            }
+
+            for j in 0..4 {
+                for i in 0..5 {
+                    for k in i + 1..5 {
+                        ipasir_add(solver, -p(i, j));
+                        ipasir_add(solver, -p(k, j));
+                        ipasir_add(solver, 0);
+                    }
+                }
+            }
+
+            ipasir_set_terminate(solver, std::ptr::null_mut(), Some(stop));
+
+            assert_eq!(ipasir_solve(solver), 0);
+
+            ipasir_set_terminate(solver, std::ptr::null_mut(), None);
+
+            assert_eq!(ipasir_solve(solver), 20);
+            assert!(learnt > 0);
+
+            ipasir_release(solver);
+        }
+    }
+}
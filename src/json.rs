@@ -0,0 +1,238 @@
+use std::fmt;
+use std::time::Duration;
+
+use crate::stats::Stats;
+use crate::Status;
+
+/// A value that can be written as JSON.
+pub trait ToJson {
+    fn write_json(&self, out: &mut String);
+
+    fn to_json(&self) -> String {
+        let mut out = String::new();
+
+        self.write_json(&mut out);
+        out
+    }
+}
+
+/// A JSON object, such as a report of the results of a command. Fields are written in the
+/// order they are added.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Object {
+    fields: Vec<(String, String)>,
+}
+
+impl Object {
+    /// Create an empty object.
+    pub fn new() -> Object {
+        Object::default()
+    }
+
+    /// Add a field, replacing the value of any field of the same key.
+    pub fn field<T>(&mut self, key: &str, value: &T) -> &mut Object
+    where
+        T: ToJson + ?Sized,
+    {
+        let value = value.to_json();
+
+        match self.fields.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.fields.push((key.to_string(), value)),
+        }
+
+        self
+    }
+}
+
+impl ToJson for Object {
+    fn write_json(&self, out: &mut String) {
+        out.push('{');
+
+        for (i, (key, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+
+            key.write_json(out);
+            out.push(':');
+            out.push_str(value);
+        }
+
+        out.push('}');
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_json())
+    }
+}
+
+impl ToJson for str {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+
+        out.push('"');
+    }
+}
+
+impl ToJson for String {
+    fn write_json(&self, out: &mut String) {
+        self.as_str().write_json(out);
+    }
+}
+
+impl ToJson for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+macro_rules! integer {
+    ($($t:ty),*) => {
+        $(
+            impl ToJson for $t {
+                fn write_json(&self, out: &mut String) {
+                    out.push_str(&self.to_string());
+                }
+            }
+        )*
+    };
+}
+
+integer!(i32, i64, u32, u64, usize);
+
+/// Numbers that are not finite have no JSON representation, and are written as `null`.
+impl ToJson for f64 {
+    fn write_json(&self, out: &mut String) {
+        if self.is_finite() {
+            out.push_str(&self.to_string());
+        } else {
+            out.push_str("null");
+        }
+    }
+}
+
+/// Durations are written in seconds.
+impl ToJson for Duration {
+    fn write_json(&self, out: &mut String) {
+        self.as_secs_f64().write_json(out);
+    }
+}
+
+impl<T: ToJson> ToJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(value) => value.write_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+impl<T: ToJson> ToJson for [T] {
+    fn write_json(&self, out: &mut String) {
+        out.push('[');
+
+        for (i, value) in self.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+
+            value.write_json(out);
+        }
+
+        out.push(']');
+    }
+}
+
+impl<T: ToJson> ToJson for Vec<T> {
+    fn write_json(&self, out: &mut String) {
+        self.as_slice().write_json(out);
+    }
+}
+
+/// Statuses are written as in the `s` lines of the SAT competition output format.
+impl ToJson for Status {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Status::Sat => "SATISFIABLE",
+            Status::Unsat => "UNSATISFIABLE",
+            Status::Unknown => "UNKNOWN",
+        }
+        .write_json(out);
+    }
+}
+
+impl ToJson for Stats {
+    fn write_json(&self, out: &mut String) {
+        Object::new()
+            .field("conflicts", &self.conflicts)
+            .field("propagations", &self.propagations)
+            .field("decisions", &self.decisions)
+            .field("restarts", &self.restarts)
+            .field("learnts", &self.learnts)
+            .field("units", &self.units)
+            .field("eliminations", &self.eliminations)
+            .field("search_time", &self.search_time)
+            .field("elimination_time", &self.elimination_time)
+            .write_json(out);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simple_01() {
+        let mut inner = Object::new();
+
+        inner.field("pivot", &-3).field("ratio", &0.5);
+
+        let mut object = Object::new();
+
+        object
+            .field("status", &Status::Unsat)
+            .field("model", &vec![1, -2])
+            .field("rounds", &vec![inner])
+            .field("cost", &None::<u64>)
+            .field("name", "a \"b\"\\\n\u{1}")
+            .field("nan", &f64::NAN)
+            .field("cost", &Some(4u64));
+
+        assert_eq!(
+            object.to_string(),
+            concat!(
+                r#"{"status":"UNSATISFIABLE","model":[1,-2],"rounds":[{"pivot":-3,"ratio":0.5}],"#,
+                r#""cost":4,"name":"a \"b\"\\\n\u0001","nan":null}"#
+            )
+        );
+    }
+
+    #[test]
+    fn stats_01() {
+        let stats = Stats {
+            conflicts: 2,
+            search_time: Duration::from_millis(1500),
+            ..Stats::default()
+        };
+
+        let json = stats.to_json();
+
+        assert!(json.starts_with(r#"{"conflicts":2,"propagations":0,"#));
+        assert!(json.ends_with(r#""search_time":1.5,"elimination_time":0}"#));
+    }
+}
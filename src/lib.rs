@@ -1,4 +1,40 @@
 #![allow(unused)]
 
+pub mod approx;
+pub mod backbone;
+pub mod bigint;
+pub mod count;
+pub mod cube;
 pub mod dimacs;
+pub mod dp;
+pub mod drat;
+pub mod encoder;
+pub mod enumerate;
+pub mod ipasir;
+pub mod json;
+pub mod maxsat;
+pub mod mus;
+pub mod opb;
+pub mod pb;
+pub mod portfolio;
+pub mod proof;
+pub mod qbf;
+pub mod rng;
+pub mod sample;
+pub mod sls;
+pub mod solution;
+pub mod solver;
+pub mod stats;
+pub mod terminator;
+#[cfg(test)]
+mod testing;
 pub mod words;
+pub mod xor;
+
+/// Outcome of a satisfiability query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Sat,
+    Unsat,
+    Unknown,
+}
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::dimacs::WeightedClause;
+use crate::encoder::Encoder;
+use crate::solver::Solver;
+use crate::terminator::Terminator;
+use crate::words::{Bits, Word};
+use crate::Status;
+
+/// Search strategy of the MaxSAT solver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Core-guided search raising a lower bound, relaxing each core with a totalizer (OLL).
+    Oll,
+
+    /// SAT-UNSAT search lowering an upper bound, comparing a binary adder over the weights of the
+    /// falsified soft clauses against the cost of the last model.
+    Linear,
+}
+
+/// Solver for weighted partial MaxSAT: find a model of the hard clauses minimizing the total
+/// weight of the falsified soft clauses.
+pub struct MaxSat {
+    num_vars: usize,
+    hard: Vec<Vec<i32>>,
+    soft: Vec<(u64, Vec<i32>)>,
+
+    algorithm: Algorithm,
+    stratify: bool,
+    deadline: Option<Instant>,
+    terminator: Option<Terminator>,
+    on_bound: Option<Box<dyn FnMut(u64)>>,
+
+    cost: Option<u64>,
+    model: Vec<i32>,
+}
+
+impl Default for MaxSat {
+    fn default() -> MaxSat {
+        MaxSat::new()
+    }
+}
+
+impl MaxSat {
+    /// Create a new, empty instance using stratified core-guided search.
+    pub fn new() -> MaxSat {
+        MaxSat {
+            num_vars: 0,
+            hard: Vec::new(),
+            soft: Vec::new(),
+
+            algorithm: Algorithm::Oll,
+            stratify: true,
+            deadline: None,
+            terminator: None,
+            on_bound: None,
+
+            cost: None,
+            model: Vec::new(),
+        }
+    }
+
+    /// The number of variables of the instance.
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// Choose the search strategy.
+    pub fn set_algorithm(&mut self, algorithm: Algorithm) {
+        self.algorithm = algorithm;
+    }
+
+    /// Enable or disable stratification, which makes core-guided search consider the soft
+    /// clauses in decreasing order of weight.
+    pub fn set_stratification(&mut self, stratify: bool) {
+        self.stratify = stratify;
+    }
+
+    /// Give up and report `Status::Unknown` once the deadline has passed. The best model found
+    /// so far remains available.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops, its conflict limit
+    /// applying to each call of the solver. The best model found so far remains available.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Call `f` with the cost of each model found that improves on the previous ones.
+    pub fn on_bound<F>(&mut self, f: F)
+    where
+        F: FnMut(u64) + 'static,
+    {
+        self.on_bound = Some(Box::new(f));
+    }
+
+    /// Add a clause as read from a `Wcnf` stream.
+    pub fn add(&mut self, clause: &WeightedClause) {
+        match clause.weight {
+            None => self.add_hard(&clause.lits),
+            Some(weight) => self.add_soft(weight, &clause.lits),
+        }
+    }
+
+    /// Add a clause that must be satisfied.
+    pub fn add_hard(&mut self, clause: &[i32]) {
+        self.reserve(clause);
+        self.hard.push(clause.to_vec());
+    }
+
+    /// Add a clause costing `weight` when falsified.
+    pub fn add_soft(&mut self, weight: u64, clause: &[i32]) {
+        if weight == 0 {
+            return;
+        }
+
+        self.reserve(clause);
+        self.soft.push((weight, clause.to_vec()));
+    }
+
+    /// Search for an optimal model. `Status::Sat` means the best model found is optimal and
+    /// `Status::Unsat` that the hard clauses are unsatisfiable.
+    pub fn solve(&mut self) -> Status {
+        self.cost = None;
+        self.model.clear();
+
+        let mut solver = Solver::new();
+
+        if let Some(deadline) = self.deadline {
+            solver.set_deadline(deadline);
+        }
+
+        if let Some(terminator) = &self.terminator {
+            solver.set_terminator(terminator.clone());
+        }
+
+        for clause in &self.hard {
+            solver.add_clause(clause);
+        }
+
+        // every soft clause is reduced to a literal that is false exactly when it costs
+        let mut num_vars = self.num_vars;
+        let mut objective = Vec::with_capacity(self.soft.len());
+
+        for (weight, clause) in &self.soft {
+            if clause.len() == 1 {
+                objective.push((*weight, clause[0]));
+            } else {
+                num_vars += 1;
+
+                let relax = num_vars as i32;
+                let mut relaxed = clause.clone();
+
+                relaxed.push(relax);
+                solver.add_clause(&relaxed);
+                objective.push((*weight, -relax));
+            }
+        }
+
+        match self.algorithm {
+            Algorithm::Oll => self.oll(solver, objective, num_vars),
+            Algorithm::Linear => self.linear(solver, objective, num_vars),
+        }
+    }
+
+    /// The cost of the best model found by the last search.
+    pub fn cost(&self) -> Option<u64> {
+        self.cost
+    }
+
+    /// The best model found by the last search, as one literal per variable.
+    pub fn model(&self) -> Vec<i32> {
+        self.model.clone()
+    }
+
+    fn reserve(&mut self, clause: &[i32]) {
+        for literal in clause {
+            self.num_vars = self.num_vars.max(literal.unsigned_abs() as usize);
+        }
+    }
+
+    /// Record the model of the solver if it is cheaper than the best one so far.
+    fn improve(&mut self, solver: &Solver) {
+        let model: Vec<i32> = (1..=self.num_vars as i32)
+            .map(|v| if solver.value(v) == Some(true) { v } else { -v })
+            .collect();
+
+        let cost = self
+            .soft
+            .iter()
+            .filter(|(_, clause)| {
+                !clause
+                    .iter()
+                    .any(|l| model[l.unsigned_abs() as usize - 1] == *l)
+            })
+            .map(|(weight, _)| weight)
+            .sum();
+
+        if matches!(self.cost, Some(best) if best <= cost) {
+            return;
+        }
+
+        self.cost = Some(cost);
+        self.model = model;
+
+        if let Some(f) = &mut self.on_bound {
+            f(cost);
+        }
+    }
+
+    /// OLL: every core of the assumed objective literals raises the lower bound by its least
+    /// weight, which is moved onto a totalizer output asserting that two of its literals are
+    /// false. Outputs in later cores are replaced by the next output of their totalizer.
+    fn oll(
+        &mut self,
+        mut solver: Solver,
+        objective: Vec<(u64, i32)>,
+        mut num_vars: usize,
+    ) -> Status {
+        let mut weights: HashMap<i32, u64> = HashMap::new();
+
+        for (weight, l) in objective {
+            *weights.entry(l).or_insert(0) += weight;
+        }
+
+        let mut totalizers: Vec<Vec<i32>> = Vec::new();
+        let mut sums: HashMap<i32, (usize, usize)> = HashMap::new();
+        let mut lower = 0;
+
+        let mut threshold = match weights.values().max() {
+            Some(max) if self.stratify => *max,
+            _ => 1,
+        };
+
+        loop {
+            let mut assumptions: Vec<i32> = weights
+                .iter()
+                .filter(|(_, weight)| **weight >= threshold)
+                .map(|(l, _)| *l)
+                .collect();
+
+            assumptions.sort_unstable();
+
+            match solver.solve_with_assumptions(&assumptions) {
+                Status::Unknown => return Status::Unknown,
+
+                Status::Sat => {
+                    self.improve(&solver);
+
+                    if self.cost == Some(lower) {
+                        return Status::Sat;
+                    }
+
+                    let next = weights
+                        .values()
+                        .filter(|weight| **weight > 0 && **weight < threshold)
+                        .max();
+
+                    match next {
+                        Some(weight) => threshold = *weight,
+                        None => return Status::Sat,
+                    }
+                }
+
+                Status::Unsat => {
+                    let core = solver.failed().to_vec();
+
+                    if core.is_empty() {
+                        return Status::Unsat;
+                    }
+
+                    let min = core.iter().map(|l| weights[l]).min().unwrap();
+
+                    lower += min;
+
+                    for l in &core {
+                        *weights.get_mut(l).unwrap() -= min;
+
+                        if let Some(&(t, k)) = sums.get(l) {
+                            if k + 1 < totalizers[t].len() {
+                                let next = -totalizers[t][k + 1];
+
+                                sums.insert(next, (t, k + 1));
+                                *weights.entry(next).or_insert(0) += min;
+                            }
+                        }
+                    }
+
+                    if core.len() == 1 {
+                        solver.add_clause(&[-core[0]]);
+                    } else {
+                        let inputs: Vec<i32> = core.iter().map(|l| -l).collect();
+                        let outputs = totalizer(&mut solver, &mut num_vars, &inputs);
+                        let next = -outputs[1];
+
+                        sums.insert(next, (totalizers.len(), 1));
+                        totalizers.push(outputs);
+                        *weights.entry(next).or_insert(0) += min;
+                    }
+
+                    weights.retain(|_, weight| *weight > 0);
+
+                    if self.cost == Some(lower) {
+                        return Status::Sat;
+                    }
+                }
+            }
+        }
+    }
+
+    /// SAT-UNSAT search: after each model, require the weighted sum of the falsified objective
+    /// literals to be less than its cost.
+    fn linear(
+        &mut self,
+        mut solver: Solver,
+        objective: Vec<(u64, i32)>,
+        num_vars: usize,
+    ) -> Status {
+        match solver.solve() {
+            Status::Sat => self.improve(&solver),
+            status => return status,
+        }
+
+        let total: u64 = objective.iter().map(|(weight, _)| weight).sum();
+        let width = (64 - total.leading_zeros()).max(1) as usize;
+
+        let bits = Bits::new();
+        let mut encoder = Encoder::new(&bits, num_vars);
+
+        let zero = Word::from_u64(&bits, width, 0);
+        let mut sum = zero.clone();
+
+        for (weight, l) in &objective {
+            let falsified = Word::var(&bits, 1);
+            let x = encoder.lit(falsified.ids()[0]);
+
+            solver.add_clause(&[x, *l]);
+            solver.add_clause(&[-x, -*l]);
+
+            sum += &Word::cond(&falsified, &Word::from_u64(&bits, width, *weight), &zero);
+        }
+
+        loop {
+            let cost = self.cost.unwrap();
+
+            if cost == 0 {
+                return Status::Sat;
+            }
+
+            let below = sum.less_than(&Word::from_u64(&bits, width, cost));
+            let l = encoder.lit(below.ids()[0]);
+
+            for clause in encoder.take_clauses() {
+                solver.add_clause(&clause);
+            }
+
+            solver.add_clause(&[l]);
+
+            match solver.solve() {
+                Status::Sat => self.improve(&solver),
+                Status::Unsat => return Status::Sat,
+                Status::Unknown => return Status::Unknown,
+            }
+        }
+    }
+}
+
+/// Build a totalizer over `inputs`, returning outputs where output `k` is implied by at least
+/// `k + 1` of the inputs being true.
+fn totalizer(solver: &mut Solver, num_vars: &mut usize, inputs: &[i32]) -> Vec<i32> {
+    if inputs.len() == 1 {
+        return inputs.to_vec();
+    }
+
+    let (left, right) = inputs.split_at(inputs.len() / 2);
+    let a = totalizer(solver, num_vars, left);
+    let b = totalizer(solver, num_vars, right);
+
+    let outputs: Vec<i32> = (0..inputs.len())
+        .map(|_| {
+            *num_vars += 1;
+            *num_vars as i32
+        })
+        .collect();
+
+    for i in 0..=a.len() {
+        for j in 0..=b.len() {
+            if i + j == 0 {
+                continue;
+            }
+
+            let mut clause = vec![outputs[i + j - 1]];
+
+            if i > 0 {
+                clause.push(-a[i - 1]);
+            }
+
+            if j > 0 {
+                clause.push(-b[j - 1]);
+            }
+
+            solver.add_clause(&clause);
+        }
+    }
+
+    outputs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs::Wcnf;
+    use crate::rng::Rng;
+    use crate::testing::random_clause;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn brute_force(num_vars: usize, hard: &[Vec<i32>], soft: &[(u64, Vec<i32>)]) -> Option<u64> {
+        let mut best = None;
+
+        for bits in 0u64..1 << num_vars {
+            let holds = |clause: &Vec<i32>| {
+                clause
+                    .iter()
+                    .any(|l| (bits >> (l.unsigned_abs() - 1) & 1 == 1) == (*l > 0))
+            };
+
+            if !hard.iter().all(holds) {
+                continue;
+            }
+
+            let cost = soft
+                .iter()
+                .filter(|(_, clause)| !holds(clause))
+                .map(|(weight, _)| weight)
+                .sum();
+
+            if best.is_none_or(|best| cost < best) {
+                best = Some(cost);
+            }
+        }
+
+        best
+    }
+
+    #[test]
+    fn wcnf_01() {
+        let text = "c x1 and x2 are exclusive, x3 follows x1
+        h -1 -2 0
+        h -1 3 0
+        5 1 0
+        3 2 0
+        4 -3 0
+        ";
+
+        let mut maxsat = MaxSat::new();
+
+        for clause in Wcnf::new(text.as_bytes()) {
+            maxsat.add(&clause);
+        }
+
+        assert_eq!(maxsat.solve(), Status::Sat);
+        assert_eq!(maxsat.cost(), Some(5));
+        assert_eq!(maxsat.model(), vec![-1, 2, -3]);
+    }
+
+    #[test]
+    fn unsat_01() {
+        let mut maxsat = MaxSat::new();
+
+        maxsat.add_hard(&[1]);
+        maxsat.add_hard(&[-1]);
+        maxsat.add_soft(1, &[2]);
+
+        assert_eq!(maxsat.solve(), Status::Unsat);
+        assert_eq!(maxsat.cost(), None);
+    }
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(7);
+
+        for round in 0..40 {
+            let mut clause = |len: usize| random_clause(&mut rng, 8, len);
+
+            let hard: Vec<Vec<i32>> = (0..8).map(|_| clause(3)).collect();
+            let soft: Vec<(u64, Vec<i32>)> = (0..12)
+                .map(|k| (1 + k % 4, clause(1 + k as usize % 2)))
+                .collect();
+
+            let expected = brute_force(8, &hard, &soft);
+
+            for (algorithm, stratify) in [
+                (Algorithm::Oll, true),
+                (Algorithm::Oll, false),
+                (Algorithm::Linear, false),
+            ] {
+                let mut maxsat = MaxSat::new();
+
+                maxsat.set_algorithm(algorithm);
+                maxsat.set_stratification(stratify);
+
+                for clause in &hard {
+                    maxsat.add_hard(clause);
+                }
+
+                for (weight, clause) in &soft {
+                    maxsat.add_soft(*weight, clause);
+                }
+
+                let status = maxsat.solve();
+
+                assert_eq!(maxsat.cost(), expected, "round {} {:?}", round, algorithm);
+
+                if expected.is_some() {
+                    assert_eq!(status, Status::Sat);
+                } else {
+                    assert_eq!(status, Status::Unsat);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bound_01() {
+        let bounds = Rc::new(RefCell::new(Vec::new()));
+        let mut maxsat = MaxSat::new();
+
+        maxsat.set_algorithm(Algorithm::Linear);
+
+        let log = Rc::clone(&bounds);
+        maxsat.on_bound(move |cost| log.borrow_mut().push(cost));
+
+        // at most one of the six variables may be true
+        for i in 1..=6 {
+            for j in i + 1..=6 {
+                maxsat.add_hard(&[-i, -j]);
+            }
+
+            maxsat.add_soft(i as u64, &[i]);
+        }
+
+        assert_eq!(maxsat.solve(), Status::Sat);
+        assert_eq!(maxsat.cost(), Some(15));
+
+        let bounds = bounds.borrow();
+
+        assert_eq!(bounds.last(), Some(&15));
+        assert!(bounds.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn word_01() {
+        let bits = Bits::new();
+        let x = Word::var(&bits, 4);
+        let y = Word::var(&bits, 4);
+
+        let differs = (&(&x + &y) ^ &Word::from_u64(&bits, 4, 10)).any();
+        let x_early = x.less_than(&Word::from_u64(&bits, 4, 3));
+        let y_early = y.less_than(&Word::from_u64(&bits, 4, 4));
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut maxsat = MaxSat::new();
+
+        // x + y = 10, preferably with x < 3 and y < 4
+        maxsat.add_hard(&[-encoder.lit(differs.ids()[0])]);
+        maxsat.add_soft(3, &[encoder.lit(x_early.ids()[0])]);
+        maxsat.add_soft(2, &[encoder.lit(y_early.ids()[0])]);
+
+        for clause in encoder.take_clauses() {
+            maxsat.add_hard(&clause);
+        }
+
+        assert_eq!(maxsat.solve(), Status::Sat);
+        assert_eq!(maxsat.cost(), Some(2));
+        assert!(encoder.decode(&x, &maxsat.model()) < 3);
+    }
+}
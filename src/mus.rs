@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::solver::Solver;
+use crate::terminator::Terminator;
+use crate::Status;
+
+/// Extraction of unsatisfiable cores and minimal unsatisfiable subsets (MUS) of labelled clauses.
+/// Clauses sharing a label form a group, kept or dropped as a whole, so that a MUS over groups
+/// explains unsatisfiability in terms of the constraints that produced the clauses. Each clause
+/// of a group is guarded by a selector variable, numbered after the variables of the clauses.
+pub struct Mus<L> {
+    num_vars: usize,
+    hard: Vec<Vec<i32>>,
+    labels: Vec<L>,
+    groups: Vec<Vec<Vec<i32>>>,
+    index: HashMap<L, usize>,
+    terminator: Option<Terminator>,
+}
+
+impl<L> Default for Mus<L>
+where
+    L: Clone + Eq + Hash,
+{
+    fn default() -> Mus<L> {
+        Mus::new()
+    }
+}
+
+impl Mus<usize> {
+    /// Create an instance labelling each clause by its index.
+    pub fn from_clauses(clauses: &[Vec<i32>]) -> Mus<usize> {
+        let mut mus = Mus::new();
+
+        for (i, clause) in clauses.iter().enumerate() {
+            mus.add_clause(i, clause);
+        }
+
+        mus
+    }
+}
+
+impl<L> Mus<L>
+where
+    L: Clone + Eq + Hash,
+{
+    /// Create a new, empty instance.
+    pub fn new() -> Mus<L> {
+        Mus {
+            num_vars: 0,
+            hard: Vec::new(),
+            labels: Vec::new(),
+            groups: Vec::new(),
+            index: HashMap::new(),
+            terminator: None,
+        }
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops, its conflict limit
+    /// applying to each call of the solver.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Add a clause that is part of every subset.
+    pub fn add_hard(&mut self, clause: &[i32]) {
+        self.reserve(clause);
+        self.hard.push(clause.to_vec());
+    }
+
+    /// Add a clause to the group of the given label.
+    pub fn add_clause(&mut self, label: L, clause: &[i32]) {
+        self.reserve(clause);
+
+        let groups = &mut self.groups;
+        let labels = &mut self.labels;
+
+        let g = *self.index.entry(label.clone()).or_insert_with(|| {
+            groups.push(Vec::new());
+            labels.push(label);
+            groups.len() - 1
+        });
+
+        self.groups[g].push(clause.to_vec());
+    }
+
+    /// The labels of an unsatisfiable subset of the groups, in the order they were first used,
+    /// or the status of the solver if the clauses are satisfiable or solving was interrupted.
+    /// The subset is not necessarily minimal, and is empty if the hard clauses alone are
+    /// unsatisfiable.
+    pub fn core(&self) -> Result<Vec<L>, Status> {
+        let (mut solver, selectors) = self.build();
+
+        match solver.solve_with_assumptions(&selectors) {
+            Status::Unsat => Ok(self.failed(&solver, &selectors)),
+            status => Err(status),
+        }
+    }
+
+    /// The labels of a minimal unsatisfiable subset of the groups, dropping one group at a time
+    /// from an initial core. A model found when a group cannot be dropped is rotated, flipping
+    /// one variable at a time, to find other groups that cannot be dropped without solving.
+    /// Fails like `core`, and with `Status::Unknown` if interrupted before the subset is minimal.
+    pub fn mus(&self) -> Result<Vec<L>, Status> {
+        let (mut solver, selectors) = self.build();
+
+        match solver.solve_with_assumptions(&selectors) {
+            Status::Unsat => {}
+            status => return Err(status),
+        }
+
+        let mut necessary = vec![false; self.groups.len()];
+        let all: Vec<usize> = (0..self.groups.len()).collect();
+        let mut candidates = shrink(&mut solver, &selectors, &necessary, &all);
+
+        while let Some(g) = candidates.iter().copied().find(|g| !necessary[*g]) {
+            let assumptions: Vec<i32> = candidates
+                .iter()
+                .filter(|h| **h != g && !necessary[**h])
+                .map(|h| selectors[*h])
+                .collect();
+
+            match solver.solve_with_assumptions(&assumptions) {
+                Status::Unsat => {
+                    candidates = shrink(&mut solver, &selectors, &necessary, &candidates);
+                }
+
+                Status::Unknown => return Err(Status::Unknown),
+
+                Status::Sat => {
+                    let model: Vec<bool> = (1..=self.num_vars as i32)
+                        .map(|v| solver.value(v) == Some(true))
+                        .collect();
+
+                    necessary[g] = true;
+                    solver.add_clause(&[selectors[g]]);
+
+                    self.rotate(
+                        &mut solver,
+                        &selectors,
+                        &candidates,
+                        &mut necessary,
+                        g,
+                        model,
+                    );
+                }
+            }
+        }
+
+        Ok(candidates.iter().map(|g| self.labels[*g].clone()).collect())
+    }
+
+    fn reserve(&mut self, clause: &[i32]) {
+        if let Some(max) = clause.iter().map(|l| l.unsigned_abs() as usize).max() {
+            self.num_vars = self.num_vars.max(max);
+        }
+    }
+
+    /// A solver for the clauses, and the selector of each group.
+    fn build(&self) -> (Solver, Vec<i32>) {
+        let mut solver = Solver::new();
+
+        if let Some(terminator) = &self.terminator {
+            solver.set_terminator(terminator.clone());
+        }
+
+        for clause in &self.hard {
+            solver.add_clause(clause);
+        }
+
+        let selectors: Vec<i32> = (0..self.groups.len())
+            .map(|g| (self.num_vars + g + 1) as i32)
+            .collect();
+
+        for (group, s) in self.groups.iter().zip(selectors.iter()) {
+            for clause in group {
+                let mut clause = clause.clone();
+
+                clause.push(-s);
+                solver.add_clause(&clause);
+            }
+        }
+
+        (solver, selectors)
+    }
+
+    fn failed(&self, solver: &Solver, selectors: &[i32]) -> Vec<L> {
+        (0..self.groups.len())
+            .filter(|g| solver.failed().contains(&selectors[*g]))
+            .map(|g| self.labels[g].clone())
+            .collect()
+    }
+
+    /// Flip each variable of the clauses of `g` falsified by `model`. Each group that becomes the
+    /// only one falsified is necessary, and its own falsified clauses are flipped in turn.
+    fn rotate(
+        &self,
+        solver: &mut Solver,
+        selectors: &[i32],
+        candidates: &[usize],
+        necessary: &mut [bool],
+        g: usize,
+        model: Vec<bool>,
+    ) {
+        let falsifies = |model: &[bool], clause: &[i32]| {
+            clause
+                .iter()
+                .all(|l| model[l.unsigned_abs() as usize - 1] != (*l > 0))
+        };
+
+        let mut pending = vec![(g, model)];
+
+        while let Some((g, model)) = pending.pop() {
+            for clause in self.groups[g].iter().filter(|c| falsifies(&model, c)) {
+                for l in clause {
+                    let mut rotated = model.clone();
+                    let v = l.unsigned_abs() as usize - 1;
+
+                    rotated[v] = !rotated[v];
+
+                    if self.hard.iter().any(|c| falsifies(&rotated, c)) {
+                        continue;
+                    }
+
+                    let falsified: Vec<usize> = candidates
+                        .iter()
+                        .copied()
+                        .filter(|h| self.groups[*h].iter().any(|c| falsifies(&rotated, c)))
+                        .take(2)
+                        .collect();
+
+                    if let [h] = falsified[..] {
+                        if !necessary[h] {
+                            necessary[h] = true;
+                            solver.add_clause(&[selectors[h]]);
+                            pending.push((h, rotated));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Keep the candidates known to be necessary or in the core of the last call to the solver, and
+/// drop the others from the solver for good.
+fn shrink(
+    solver: &mut Solver,
+    selectors: &[i32],
+    necessary: &[bool],
+    candidates: &[usize],
+) -> Vec<usize> {
+    let (kept, dropped): (Vec<usize>, Vec<usize>) = candidates
+        .iter()
+        .partition(|g| necessary[**g] || solver.failed().contains(&selectors[**g]));
+
+    for g in dropped {
+        solver.add_clause(&[-selectors[g]]);
+    }
+
+    kept
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::testing::random_3sat;
+
+    fn satisfiable(clauses: &[Vec<i32>]) -> bool {
+        let mut solver = Solver::new();
+
+        for clause in clauses {
+            solver.add_clause(clause);
+        }
+
+        solver.solve() == Status::Sat
+    }
+
+    #[test]
+    fn simple_01() {
+        let clauses = vec![
+            vec![1],
+            vec![3],
+            vec![-1, 2],
+            vec![-3, 4],
+            vec![-2],
+            vec![1, 2, 3],
+        ];
+
+        let mus = Mus::from_clauses(&clauses);
+
+        assert_eq!(mus.mus(), Ok(vec![0, 2, 4]));
+
+        let core = mus.core().unwrap();
+
+        assert!(!satisfiable(
+            &core.iter().map(|i| clauses[*i].clone()).collect::<Vec<_>>()
+        ));
+
+        let mus = Mus::from_clauses(&clauses[..4]);
+
+        assert_eq!(mus.core(), Err(Status::Sat));
+        assert_eq!(mus.mus(), Err(Status::Sat));
+
+        // a stop requested beforehand is honoured
+        let mut mus = Mus::from_clauses(&clauses);
+        let terminator = Terminator::new();
+
+        terminator.terminate();
+        mus.set_terminator(terminator);
+
+        assert_eq!(mus.mus(), Err(Status::Unknown));
+    }
+
+    #[test]
+    fn groups_01() {
+        let mut mus = Mus::new();
+
+        // a = 1 and a = 2 conflict, b = 3 is unrelated
+        mus.add_hard(&[1, 2]);
+        mus.add_clause("a is 1", &[1]);
+        mus.add_clause("a is 1", &[-2]);
+        mus.add_clause("b is 3", &[3]);
+        mus.add_clause("a is 2", &[-1]);
+        mus.add_clause("a is 2", &[2]);
+
+        assert_eq!(mus.mus(), Ok(vec!["a is 1", "a is 2"]));
+    }
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(5);
+
+        for _ in 0..30 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 10, 60);
+
+            let subset = match Mus::from_clauses(&clauses).mus() {
+                Ok(subset) => subset,
+                Err(_) => {
+                    assert!(satisfiable(&clauses));
+                    continue;
+                }
+            };
+
+            let selected: Vec<Vec<i32>> = subset.iter().map(|i| clauses[*i].clone()).collect();
+
+            assert!(!satisfiable(&selected));
+
+            // dropping any clause makes the subset satisfiable
+            for i in 0..selected.len() {
+                let mut rest = selected.clone();
+                rest.remove(i);
+
+                assert!(satisfiable(&rest));
+            }
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::io::{BufRead, Write};
+use std::iter::FusedIterator;
+
+/// Comparison of a pseudo-Boolean constraint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    Ge,
+    Le,
+    Eq,
+}
+
+/// A linear pseudo-Boolean constraint `Σ coefficient * literal <relation> rhs`. Literals are in
+/// DIMACS convention, a negative literal standing for `1 - x`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    pub terms: Vec<(i64, i32)>,
+    pub relation: Relation,
+    pub rhs: i64,
+}
+
+/// Iterator to produce constraints from an OPB formatted `BufRead` stream. Variable `xN` is the
+/// literal `N` and `~xN` its complement. The objective function is collected as it is read.
+pub struct Opb<R> {
+    io: R,
+    line: String,
+    header: Option<(usize, usize)>,
+    objective: Option<Vec<(i64, i32)>>,
+}
+
+impl<R> Opb<R>
+where
+    R: BufRead,
+{
+    /// Create a new `Opb<R>` structure with the given `BufRead` stream.
+    pub fn new(io: R) -> Opb<R> {
+        Opb {
+            io,
+            line: String::new(),
+            header: None,
+            objective: None,
+        }
+    }
+
+    /// The number of variables and constraints declared by the `* #variable=` comment, once it
+    /// has been read.
+    pub fn header(&self) -> Option<(usize, usize)> {
+        self.header
+    }
+
+    /// The terms of the `min:` objective function, once it has been read.
+    pub fn objective(&self) -> Option<&[(i64, i32)]> {
+        self.objective.as_deref()
+    }
+}
+
+impl<R> FusedIterator for Opb<R> where R: BufRead {}
+
+impl<R> Iterator for Opb<R>
+where
+    R: BufRead,
+{
+    type Item = Constraint;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+
+            let bytes = self.io.read_line(&mut self.line).unwrap();
+
+            if bytes == 0 {
+                break;
+            }
+
+            let line = self.line.trim();
+
+            if let Some(comment) = line.strip_prefix('*') {
+                let tokens: Vec<&str> = comment.split_whitespace().collect();
+
+                if let ["#variable=", vars, "#constraint=", constraints, ..] = tokens[..] {
+                    self.header = Some((vars.parse().unwrap(), constraints.parse().unwrap()));
+                }
+
+                continue;
+            }
+
+            let line = line.trim_end_matches(';').trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(objective) = line.strip_prefix("min:") {
+                self.objective = Some(parse_terms(objective.split_whitespace()));
+                continue;
+            }
+
+            let mut tokens: Vec<&str> = line.split_whitespace().collect();
+
+            let rhs = tokens.pop().unwrap().parse().unwrap();
+
+            let relation = match tokens.pop().unwrap() {
+                ">=" => Relation::Ge,
+                "<=" => Relation::Le,
+                "=" => Relation::Eq,
+                other => panic!("unknown relation '{}'", other),
+            };
+
+            return Some(Constraint {
+                terms: parse_terms(tokens.into_iter()),
+                relation,
+                rhs,
+            });
+        }
+
+        None
+    }
+}
+
+/// Parse pairs of a coefficient and a literal. Products of literals are not supported.
+fn parse_terms<'a, I>(tokens: I) -> Vec<(i64, i32)>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let tokens: Vec<&str> = tokens.collect();
+
+    tokens
+        .chunks(2)
+        .map(|term| match term {
+            [coefficient, literal] => {
+                let coefficient = coefficient.trim_start_matches('+').parse().unwrap();
+
+                let literal = match literal.strip_prefix('~') {
+                    Some(var) => -var.trim_start_matches('x').parse::<i32>().unwrap(),
+                    None => literal.trim_start_matches('x').parse::<i32>().unwrap(),
+                };
+
+                (coefficient, literal)
+            }
+
+            _ => panic!("malformed term {:?}", term),
+        })
+        .collect()
+}
+
+/// Write constraints and an optional objective to a stream in OPB format.
+pub fn write_opb<W>(
+    io: &mut W,
+    num_vars: usize,
+    objective: Option<&[(i64, i32)]>,
+    constraints: &[Constraint],
+) -> std::io::Result<()>
+where
+    W: Write,
+{
+    writeln!(
+        io,
+        "* #variable= {} #constraint= {}",
+        num_vars,
+        constraints.len()
+    )?;
+
+    if let Some(objective) = objective {
+        write!(io, "min:")?;
+        write_terms(io, objective)?;
+        writeln!(io, " ;")?;
+    }
+
+    for constraint in constraints {
+        write_terms(io, &constraint.terms)?;
+
+        let relation = match constraint.relation {
+            Relation::Ge => ">=",
+            Relation::Le => "<=",
+            Relation::Eq => "=",
+        };
+
+        writeln!(io, " {} {} ;", relation, constraint.rhs)?;
+    }
+
+    Ok(())
+}
+
+fn write_terms<W: Write>(io: &mut W, terms: &[(i64, i32)]) -> std::io::Result<()> {
+    for (coefficient, literal) in terms {
+        let negation = if *literal < 0 { "~" } else { "" };
+
+        write!(io, " {:+} {}x{}", coefficient, negation, literal.abs())?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simple_01() {
+        let text = "* #variable= 3 #constraint= 2
+        * a comment
+        min: +1 x1 -2 x2 ;
+        +1 x1 +2 ~x2 +3 x3 >= 2 ;
+        -1 x1 +1 x3 = 0 ;
+        ";
+
+        let mut opb = Opb::new(text.as_bytes());
+        let constraints: Vec<Constraint> = opb.by_ref().collect();
+
+        assert_eq!(opb.header(), Some((3, 2)));
+        assert_eq!(opb.objective(), Some(&[(1, 1), (-2, 2)][..]));
+        assert_eq!(
+            constraints,
+            vec![
+                Constraint {
+                    terms: vec![(1, 1), (2, -2), (3, 3)],
+                    relation: Relation::Ge,
+                    rhs: 2,
+                },
+                Constraint {
+                    terms: vec![(-1, 1), (1, 3)],
+                    relation: Relation::Eq,
+                    rhs: 0,
+                },
+            ]
+        );
+
+        let mut out = Vec::new();
+        write_opb(&mut out, 3, opb.objective(), &constraints).unwrap();
+
+        let mut opb = Opb::new(&out[..]);
+
+        assert_eq!(opb.by_ref().collect::<Vec<_>>(), constraints);
+        assert_eq!(opb.objective(), Some(&[(1, 1), (-2, 2)][..]));
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::opb::{Constraint, Relation};
+
+/// Translation of pseudo-Boolean constraints to clauses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    /// Binary decision diagram over the terms, sharing nodes with the same remaining bound.
+    Bdd,
+
+    /// Odd-even merge sorting network over the coefficients expanded in unary, suited to small
+    /// coefficients.
+    Sorter,
+
+    /// Generalized totalizer: a tree of adders keeping one output per reachable partial sum.
+    Totalizer,
+}
+
+#[derive(Clone, Copy)]
+enum Node {
+    True,
+    False,
+    Var(i32),
+}
+
+/// Translate a constraint to clauses. Auxiliary variables are numbered after `num_vars`, which
+/// is updated. An unsatisfiable constraint yields the empty clause.
+pub fn encode(constraint: &Constraint, encoding: Encoding, num_vars: &mut usize) -> Vec<Vec<i32>> {
+    let mut clauses = Vec::new();
+
+    for (terms, bound) in at_most(constraint) {
+        let total: u64 = terms.iter().map(|(a, _)| a).sum();
+
+        if bound < 0 {
+            clauses.push(vec![]);
+            continue;
+        }
+
+        let bound = bound as u64;
+
+        if total <= bound {
+            continue;
+        }
+
+        // a coefficient above the bound is as good as one just above it
+        let terms: Vec<(u64, i32)> = terms
+            .into_iter()
+            .map(|(a, l)| (a.min(bound + 1), l))
+            .collect();
+
+        match encoding {
+            Encoding::Bdd => bdd(&terms, bound, num_vars, &mut clauses),
+            Encoding::Sorter => sorter(&terms, bound, num_vars, &mut clauses),
+            Encoding::Totalizer => totalizer(&terms, bound, num_vars, &mut clauses),
+        }
+    }
+
+    clauses
+}
+
+/// Reduce an objective to minimize to a constant and soft unit clauses, each costing its
+/// weight when falsified.
+pub fn objective(terms: &[(i64, i32)]) -> (i64, Vec<(u64, i32)>) {
+    let mut offset = 0;
+    let mut soft = Vec::with_capacity(terms.len());
+
+    for (c, l) in terms {
+        match c.signum() {
+            1 => soft.push((*c as u64, -l)),
+            -1 => {
+                offset += c;
+                soft.push((c.unsigned_abs(), *l));
+            }
+            _ => {}
+        }
+    }
+
+    (offset, soft)
+}
+
+/// Rewrite a constraint as constraints `Σ a * l <= bound` with positive coefficients.
+fn at_most(constraint: &Constraint) -> Vec<(Vec<(u64, i32)>, i64)> {
+    let negated: Vec<(i64, i32)> = constraint.terms.iter().map(|(c, l)| (-c, *l)).collect();
+
+    let forms = match constraint.relation {
+        Relation::Le => vec![(constraint.terms.clone(), constraint.rhs)],
+        Relation::Ge => vec![(negated, -constraint.rhs)],
+        Relation::Eq => vec![
+            (constraint.terms.clone(), constraint.rhs),
+            (negated, -constraint.rhs),
+        ],
+    };
+
+    forms
+        .into_iter()
+        .map(|(terms, mut bound)| {
+            let mut positive = Vec::with_capacity(terms.len());
+
+            // c * l = c + |c| * -l for negative c
+            for (c, l) in terms {
+                if c < 0 {
+                    bound -= c;
+                    positive.push((c.unsigned_abs(), -l));
+                } else if c > 0 {
+                    positive.push((c as u64, l));
+                }
+            }
+
+            (positive, bound)
+        })
+        .collect()
+}
+
+fn fresh(num_vars: &mut usize) -> i32 {
+    *num_vars += 1;
+    *num_vars as i32
+}
+
+/// Each node stands for the remaining terms summing to at most the remaining bound, and is
+/// implied by its parent.
+fn bdd(terms: &[(u64, i32)], bound: u64, num_vars: &mut usize, clauses: &mut Vec<Vec<i32>>) {
+    let mut suffix = vec![0; terms.len() + 1];
+
+    for i in (0..terms.len()).rev() {
+        suffix[i] = suffix[i + 1] + terms[i].0;
+    }
+
+    let mut memo = HashMap::new();
+
+    match bdd_node(
+        terms,
+        &suffix,
+        0,
+        bound as i64,
+        num_vars,
+        clauses,
+        &mut memo,
+    ) {
+        Node::True => {}
+        Node::False => clauses.push(vec![]),
+        Node::Var(v) => clauses.push(vec![v]),
+    }
+}
+
+fn bdd_node(
+    terms: &[(u64, i32)],
+    suffix: &[u64],
+    i: usize,
+    remaining: i64,
+    num_vars: &mut usize,
+    clauses: &mut Vec<Vec<i32>>,
+    memo: &mut HashMap<(usize, i64), Node>,
+) -> Node {
+    if remaining < 0 {
+        return Node::False;
+    }
+
+    if suffix[i] <= remaining as u64 {
+        return Node::True;
+    }
+
+    if let Some(node) = memo.get(&(i, remaining)) {
+        return *node;
+    }
+
+    let (a, l) = terms[i];
+    let high = bdd_node(
+        terms,
+        suffix,
+        i + 1,
+        remaining - a as i64,
+        num_vars,
+        clauses,
+        memo,
+    );
+    let low = bdd_node(terms, suffix, i + 1, remaining, num_vars, clauses, memo);
+
+    let v = fresh(num_vars);
+
+    match high {
+        Node::True => {}
+        Node::False => clauses.push(vec![-v, -l]),
+        Node::Var(h) => clauses.push(vec![-v, -l, h]),
+    }
+
+    if let Node::Var(w) = low {
+        clauses.push(vec![-v, w]);
+    }
+
+    memo.insert((i, remaining), Node::Var(v));
+
+    Node::Var(v)
+}
+
+/// Sort the coefficients, expanded to that many copies of their literal, and forbid the output
+/// counting `bound + 1` true inputs. Wires of `None` are constant false.
+fn sorter(terms: &[(u64, i32)], bound: u64, num_vars: &mut usize, clauses: &mut Vec<Vec<i32>>) {
+    let mut wires: Vec<Option<i32>> = terms
+        .iter()
+        .flat_map(|(a, l)| std::iter::repeat_n(Some(*l), *a as usize))
+        .collect();
+
+    wires.resize(wires.len().next_power_of_two(), None);
+
+    sort(&mut wires, num_vars, clauses);
+
+    if let Some(l) = wires[bound as usize] {
+        clauses.push(vec![-l]);
+    }
+}
+
+fn sort(wires: &mut [Option<i32>], num_vars: &mut usize, clauses: &mut Vec<Vec<i32>>) {
+    if wires.len() <= 1 {
+        return;
+    }
+
+    let half = wires.len() / 2;
+
+    sort(&mut wires[..half], num_vars, clauses);
+    sort(&mut wires[half..], num_vars, clauses);
+    merge(wires, 0, 1, num_vars, clauses);
+}
+
+/// Batcher's odd-even merge of the two sorted halves of the wires `lo`, `lo + r`, ...
+fn merge(
+    wires: &mut [Option<i32>],
+    lo: usize,
+    r: usize,
+    num_vars: &mut usize,
+    clauses: &mut Vec<Vec<i32>>,
+) {
+    let step = 2 * r;
+
+    if step >= wires.len() {
+        compare(wires, lo, lo + r, num_vars, clauses);
+        return;
+    }
+
+    merge(wires, lo, step, num_vars, clauses);
+    merge(wires, lo + r, step, num_vars, clauses);
+
+    for i in (lo + r..lo + wires.len() - r).step_by(step) {
+        compare(wires, i, i + r, num_vars, clauses);
+    }
+}
+
+/// Put the disjunction of two wires on the first and their conjunction on the second. Only the
+/// implications from inputs to outputs are needed to count true inputs.
+fn compare(
+    wires: &mut [Option<i32>],
+    i: usize,
+    j: usize,
+    num_vars: &mut usize,
+    clauses: &mut Vec<Vec<i32>>,
+) {
+    let (a, b) = match (wires[i], wires[j]) {
+        (Some(a), Some(b)) => (a, b),
+        (a, b) => {
+            wires[i] = a.or(b);
+            wires[j] = None;
+            return;
+        }
+    };
+
+    let high = fresh(num_vars);
+    let low = fresh(num_vars);
+
+    clauses.push(vec![-a, high]);
+    clauses.push(vec![-b, high]);
+    clauses.push(vec![-a, -b, low]);
+
+    wires[i] = Some(high);
+    wires[j] = Some(low);
+}
+
+/// Build the tree of partial sums, capped at `bound + 1`, and forbid the capped sum at the root.
+fn totalizer(terms: &[(u64, i32)], bound: u64, num_vars: &mut usize, clauses: &mut Vec<Vec<i32>>) {
+    let root = totalizer_node(terms, bound + 1, num_vars, clauses);
+
+    if let Some(v) = root.get(&(bound + 1)) {
+        clauses.push(vec![-v]);
+    }
+}
+
+fn totalizer_node(
+    terms: &[(u64, i32)],
+    cap: u64,
+    num_vars: &mut usize,
+    clauses: &mut Vec<Vec<i32>>,
+) -> BTreeMap<u64, i32> {
+    if let [(a, l)] = terms {
+        return BTreeMap::from([(*a, *l)]);
+    }
+
+    let (left, right) = terms.split_at(terms.len() / 2);
+    let left = totalizer_node(left, cap, num_vars, clauses);
+    let right = totalizer_node(right, cap, num_vars, clauses);
+
+    let mut sums = BTreeMap::new();
+    let mut output =
+        |s: u64, num_vars: &mut usize| *sums.entry(s.min(cap)).or_insert_with(|| fresh(num_vars));
+
+    for (s, v) in left.iter().chain(right.iter()) {
+        let o = output(*s, num_vars);
+        clauses.push(vec![-v, o]);
+    }
+
+    for (s, v) in &left {
+        for (t, w) in &right {
+            let o = output(s + t, num_vars);
+            clauses.push(vec![-v, -w, o]);
+        }
+    }
+
+    sums
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::rng::Rng;
+    use crate::solver::Solver;
+    use crate::Status;
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(3);
+
+        for round in 0..60 {
+            let terms: Vec<(i64, i32)> = (1..=6)
+                .map(|v| {
+                    let c = rng.below(9) as i64 - 4;
+                    (c, if rng.next_bool() { v } else { -v })
+                })
+                .collect();
+
+            let relation = match round % 3 {
+                0 => Relation::Ge,
+                1 => Relation::Le,
+                _ => Relation::Eq,
+            };
+
+            let constraint = Constraint {
+                terms,
+                relation,
+                rhs: rng.below(9) as i64 - 4,
+            };
+
+            for encoding in [Encoding::Bdd, Encoding::Sorter, Encoding::Totalizer] {
+                let mut num_vars = 6;
+                let mut solver = Solver::new();
+
+                for clause in encode(&constraint, encoding, &mut num_vars) {
+                    solver.add_clause(&clause);
+                }
+
+                // the clauses must admit exactly the assignments satisfying the constraint
+                for bits in 0..64 {
+                    let assignment: Vec<i32> = (1..=6)
+                        .map(|v| if bits >> (v - 1) & 1 == 1 { v } else { -v })
+                        .collect();
+
+                    let sum: i64 = constraint
+                        .terms
+                        .iter()
+                        .filter(|(_, l)| assignment.contains(l))
+                        .map(|(c, _)| c)
+                        .sum();
+
+                    let holds = match relation {
+                        Relation::Ge => sum >= constraint.rhs,
+                        Relation::Le => sum <= constraint.rhs,
+                        Relation::Eq => sum == constraint.rhs,
+                    };
+
+                    let expected = if holds { Status::Sat } else { Status::Unsat };
+
+                    assert_eq!(
+                        solver.solve_with_assumptions(&assignment),
+                        expected,
+                        "{:?} {:?} {:?}",
+                        constraint,
+                        encoding,
+                        assignment
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn objective_01() {
+        let (offset, soft) = objective(&[(3, 1), (-2, 2), (0, 3)]);
+
+        assert_eq!(offset, -2);
+        assert_eq!(soft, vec![(3, -1), (2, 2)]);
+    }
+}
@@ -0,0 +1,330 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use crate::sls::LocalSearch;
+use crate::solver::{Restarts, Solver};
+use crate::stats::Stats;
+use crate::terminator::Terminator;
+use crate::Status;
+
+/// The configuration of one solver of a portfolio.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    pub seed: u64,
+    pub restarts: Restarts,
+
+    /// Start from the phases of the best assignment found by local search, or from its model
+    /// if it finds one.
+    pub local_search: bool,
+}
+
+impl Config {
+    /// `n` configurations differing in seed, and alternating between Luby and geometric
+    /// restarts, with and without local search.
+    pub fn diverse(n: usize) -> Vec<Config> {
+        (0..n as u64)
+            .map(|i| Config {
+                seed: i,
+                restarts: match i % 2 {
+                    0 => Restarts::Luby(100),
+                    _ => Restarts::Geometric(100, 1.5),
+                },
+                local_search: i % 4 == 3,
+            })
+            .collect()
+    }
+}
+
+/// Clauses learnt by the solvers of a portfolio and not yet imported, one queue per solver.
+type Shared = Arc<Vec<Mutex<Vec<Vec<i32>>>>>;
+
+/// Portfolio of differently configured solvers running in parallel threads on the same clauses.
+/// The solvers share the short clauses they learn, and the first to reach a conclusion stops
+/// the others.
+///
+/// Only clauses and XOR constraints are given to the portfolio, so that it can be sent to the
+/// threads; words are encoded first with an `Encoder`.
+pub struct Portfolio {
+    configs: Vec<Config>,
+    clauses: Vec<Vec<i32>>,
+    xors: Vec<Vec<i32>>,
+    max_length: usize,
+    deadline: Option<Instant>,
+    terminator: Option<Terminator>,
+
+    model: Vec<i32>,
+    winner: Option<usize>,
+    stats: Stats,
+}
+
+impl Portfolio {
+    /// Create a portfolio of `threads` diverse solvers.
+    pub fn new(threads: usize) -> Portfolio {
+        assert!(threads > 0);
+
+        Portfolio {
+            configs: Config::diverse(threads),
+            clauses: Vec::new(),
+            xors: Vec::new(),
+            max_length: 8,
+            deadline: None,
+            terminator: None,
+
+            model: Vec::new(),
+            winner: None,
+            stats: Stats::default(),
+        }
+    }
+
+    /// Run one solver per configuration instead.
+    pub fn set_configs(&mut self, configs: Vec<Config>) {
+        assert!(!configs.is_empty());
+
+        self.configs = configs;
+    }
+
+    /// Share learnt clauses of at most `max_length` literals, 8 by default. Units are always
+    /// shared.
+    pub fn set_share_length(&mut self, max_length: usize) {
+        self.max_length = max_length.max(1);
+    }
+
+    /// Give up and report `Status::Unknown` once the deadline has passed.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops, its conflict limit
+    /// applying to each solver.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Add a clause.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        self.clauses.push(clause.to_vec());
+    }
+
+    /// Add an XOR constraint, given as literals whose exclusive or is true.
+    pub fn add_xor(&mut self, xor: &[i32]) {
+        self.xors.push(xor.to_vec());
+    }
+
+    /// Solve with every solver at once, returning the first conclusion reached.
+    pub fn solve(&mut self) -> Status {
+        let stop = Arc::new(AtomicBool::new(false));
+        let shared: Shared = Arc::new(self.configs.iter().map(|_| Mutex::default()).collect());
+        let result: Mutex<Option<(usize, Status, Vec<i32>)>> = Mutex::new(None);
+        let stats = Mutex::new(Stats::default());
+
+        thread::scope(|scope| {
+            for (i, config) in self.configs.iter().enumerate() {
+                let mut solver = self.solver(i, config, &stop, &shared);
+                let stop = &stop;
+                let result = &result;
+                let stats = &stats;
+                let seed = config.seed;
+                let only_clauses = self.xors.is_empty();
+
+                let search = if config.local_search {
+                    Some(LocalSearch::new(&self.clauses))
+                } else {
+                    None
+                };
+
+                scope.spawn(move || {
+                    let mut status = Status::Unknown;
+                    let mut model = Vec::new();
+
+                    if let Some(mut search) = search {
+                        search.set_seed(seed);
+                        search.set_limits(100_000, 1);
+
+                        // local search ignores XOR constraints, so only its phases are of use
+                        // with them
+                        if search.solve() == Status::Sat && only_clauses {
+                            status = Status::Sat;
+                            model = search.model();
+                        } else {
+                            search.set_phases(&mut solver);
+                        }
+                    }
+
+                    if status == Status::Unknown {
+                        status = solver.solve();
+                        model = solver.model();
+                    }
+
+                    stats.lock().unwrap().merge(solver.stats());
+
+                    if status != Status::Unknown && !stop.swap(true, Ordering::SeqCst) {
+                        *result.lock().unwrap() = Some((i, status, model));
+                    }
+                });
+            }
+        });
+
+        self.stats = stats.into_inner().unwrap();
+
+        match result.into_inner().unwrap() {
+            Some((i, status, model)) => {
+                self.winner = Some(i);
+                self.model = model;
+                status
+            }
+
+            None => {
+                self.winner = None;
+                self.model.clear();
+                Status::Unknown
+            }
+        }
+    }
+
+    /// The model found, as one literal per variable.
+    pub fn model(&self) -> &[i32] {
+        &self.model
+    }
+
+    /// The index of the configuration of the solver reaching the conclusion, if any.
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// The statistics of the last call to `solve`, added up over every solver.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    fn solver(&self, i: usize, config: &Config, stop: &Arc<AtomicBool>, shared: &Shared) -> Solver {
+        let mut solver = Solver::new();
+
+        solver.set_seed(config.seed);
+        solver.set_restarts(config.restarts);
+
+        if let Some(deadline) = self.deadline {
+            solver.set_deadline(deadline);
+        }
+
+        if let Some(terminator) = &self.terminator {
+            solver.set_terminator(terminator.clone());
+        }
+
+        for clause in &self.clauses {
+            solver.add_clause(clause);
+        }
+
+        for xor in &self.xors {
+            solver.add_xor(xor);
+        }
+
+        let stop = stop.clone();
+
+        solver.set_terminate(Some(Box::new(move || stop.load(Ordering::Relaxed))));
+
+        let learnt = shared.clone();
+
+        solver.set_learn(
+            self.max_length,
+            Some(Box::new(move |clause| {
+                for (j, queue) in learnt.iter().enumerate() {
+                    if j != i {
+                        queue.lock().unwrap().push(clause.to_vec());
+                    }
+                }
+            })),
+        );
+
+        // clauses learnt by the others since the last import
+        let shared = shared.clone();
+
+        solver.set_import(Some(Box::new(move || {
+            std::mem::take(&mut *shared[i].lock().unwrap())
+        })));
+
+        solver
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::encoder::Encoder;
+    use crate::rng::Rng;
+    use crate::testing::{pigeonhole, random_3sat};
+    use crate::words::{Bits, Word};
+    use std::time::Duration;
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(12);
+
+        for round in 0..20 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 50, 200 + round * 5);
+
+            let mut solver = Solver::new();
+            let mut portfolio = Portfolio::new(4);
+
+            for clause in &clauses {
+                solver.add_clause(clause);
+                portfolio.add_clause(clause);
+            }
+
+            let status = portfolio.solve();
+
+            assert_eq!(status, solver.solve());
+
+            if status == Status::Sat {
+                let model = portfolio.model();
+
+                assert!(clauses.iter().all(|c| c.iter().any(|l| model.contains(l))));
+            }
+        }
+    }
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 12);
+        let b = Word::var(&bits, 12);
+        let target = Word::from_u64(&bits, 12, 3127);
+        let differs = (&(&a * &b) ^ &target).any();
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut portfolio = Portfolio::new(3);
+
+        // a nontrivial factorization of 3127 = 53 * 59
+        portfolio.add_clause(&[-encoder.lit(differs.ids()[0])]);
+        portfolio.add_clause(&[-encoder.lit(a.less_than(&Word::from_u64(&bits, 12, 2)).ids()[0])]);
+        portfolio.add_clause(&[-encoder.lit(b.less_than(&Word::from_u64(&bits, 12, 2)).ids()[0])]);
+
+        for clause in encoder.take_clauses() {
+            portfolio.add_clause(&clause);
+        }
+
+        assert_eq!(portfolio.solve(), Status::Sat);
+
+        let (x, y) = (
+            encoder.decode(&a, portfolio.model()),
+            encoder.decode(&b, portfolio.model()),
+        );
+
+        assert_eq!(x * y % 4096, 3127);
+        assert!(x > 1 && y > 1);
+    }
+
+    #[test]
+    fn stats_01() {
+        let mut portfolio = Portfolio::new(2);
+
+        for clause in pigeonhole(5) {
+            portfolio.add_clause(&clause);
+        }
+
+        assert_eq!(portfolio.solve(), Status::Unsat);
+        assert!(portfolio.stats().conflicts > 0);
+        assert!(portfolio.stats().search_time > Duration::default());
+    }
+}
@@ -0,0 +1,984 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// A step of a DRAT proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Step {
+    Add(Vec<i32>),
+    Delete(Vec<i32>),
+}
+
+/// A line of an LRAT proof. Hints are clause ids; in a RAT step a negative hint `-id` names the
+/// clause containing the complement of the pivot whose resolvent the following hints refute.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Lrat {
+    Add {
+        id: u64,
+        clause: Vec<i32>,
+        hints: Vec<i64>,
+    },
+    Delete {
+        id: u64,
+        ids: Vec<u64>,
+    },
+}
+
+/// Result of a successful DRAT check: the indices of the formula clauses needed to refute it
+/// and a trimmed LRAT proof of the refutation.
+#[derive(Clone, Debug)]
+pub struct Certificate {
+    pub core: Vec<usize>,
+    pub lrat: Vec<Lrat>,
+}
+
+/// Reasons for rejecting a proof.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckError {
+    /// The proof never refutes the formula.
+    NoConflict,
+    /// The DRAT step at this index is neither RUP nor RAT.
+    Lemma(usize),
+    /// The hints of the LRAT clause with this id do not justify it.
+    Hint(u64),
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Read a DRAT proof, detecting whether it uses the text or binary format.
+pub fn read_drat<R>(mut io: R) -> io::Result<Vec<Step>>
+where
+    R: BufRead,
+{
+    let mut bytes = Vec::new();
+
+    io.read_to_end(&mut bytes)?;
+
+    let binary = bytes
+        .iter()
+        .take(10)
+        .any(|b| !b" \t\r\n-0123456789cd".contains(b));
+
+    if binary {
+        read_binary(&bytes)
+    } else {
+        read_text(&String::from_utf8_lossy(&bytes))
+    }
+}
+
+fn read_text(text: &str) -> io::Result<Vec<Step>> {
+    let mut steps = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('c') {
+            continue;
+        }
+
+        let (delete, line) = match line.strip_prefix('d') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+
+        let mut clause = Vec::new();
+
+        for token in line.split_whitespace() {
+            let literal: i32 = token
+                .parse()
+                .map_err(|_| invalid(format!("invalid literal '{}'", token)))?;
+
+            if literal == 0 {
+                break;
+            }
+
+            clause.push(literal);
+        }
+
+        steps.push(if delete {
+            Step::Delete(clause)
+        } else {
+            Step::Add(clause)
+        });
+    }
+
+    Ok(steps)
+}
+
+fn read_binary(bytes: &[u8]) -> io::Result<Vec<Step>> {
+    let mut steps = Vec::new();
+    let mut bytes = bytes.iter();
+
+    while let Some(kind) = bytes.next() {
+        let mut clause = Vec::new();
+
+        loop {
+            let mut n: u32 = 0;
+            let mut shift = 0;
+
+            loop {
+                let byte = *bytes
+                    .next()
+                    .ok_or_else(|| invalid("truncated binary proof".to_string()))?;
+
+                n |= ((byte & 0x7f) as u32) << shift;
+                shift += 7;
+
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+
+            if n == 0 {
+                break;
+            }
+
+            let v = (n >> 1) as i32;
+            clause.push(if n & 1 == 1 { -v } else { v });
+        }
+
+        steps.push(match kind {
+            b'a' => Step::Add(clause),
+            b'd' => Step::Delete(clause),
+            _ => return Err(invalid(format!("unknown proof step 0x{:02x}", kind))),
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Read an LRAT proof in text format.
+pub fn read_lrat<R>(io: R) -> io::Result<Vec<Lrat>>
+where
+    R: BufRead,
+{
+    let mut steps = Vec::new();
+
+    for line in io.lines() {
+        let line = line?;
+        let mut tokens = line.split_whitespace().peekable();
+
+        let id: u64 = match tokens.next() {
+            None => continue,
+            Some("c") => continue,
+            Some(token) => token
+                .parse()
+                .map_err(|_| invalid(format!("invalid clause id '{}'", token)))?,
+        };
+
+        let mut numbers = |tokens: &mut dyn Iterator<Item = &str>| -> io::Result<Vec<i64>> {
+            let mut numbers = Vec::new();
+
+            for token in tokens {
+                let n: i64 = token
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid number '{}'", token)))?;
+
+                if n == 0 {
+                    break;
+                }
+
+                numbers.push(n);
+            }
+
+            Ok(numbers)
+        };
+
+        if tokens.peek() == Some(&"d") {
+            tokens.next();
+
+            let ids = numbers(&mut tokens)?
+                .into_iter()
+                .map(|n| n as u64)
+                .collect();
+            steps.push(Lrat::Delete { id, ids });
+        } else {
+            let clause = numbers(&mut tokens)?
+                .into_iter()
+                .map(|n| n as i32)
+                .collect();
+            let hints = numbers(&mut tokens)?;
+
+            steps.push(Lrat::Add { id, clause, hints });
+        }
+    }
+
+    Ok(steps)
+}
+
+/// Write an LRAT proof in text format.
+pub fn write_lrat<W>(io: &mut W, steps: &[Lrat]) -> io::Result<()>
+where
+    W: Write,
+{
+    for step in steps {
+        match step {
+            Lrat::Add { id, clause, hints } => {
+                write!(io, "{} ", id)?;
+
+                for literal in clause {
+                    write!(io, "{} ", literal)?;
+                }
+
+                write!(io, "0 ")?;
+
+                for hint in hints {
+                    write!(io, "{} ", hint)?;
+                }
+
+                writeln!(io, "0")?;
+            }
+
+            Lrat::Delete { id, ids } => {
+                write!(io, "{} d ", id)?;
+
+                for i in ids {
+                    write!(io, "{} ", i)?;
+                }
+
+                writeln!(io, "0")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Index of a literal in watch lists: `2 * |l| + (l < 0)`.
+fn index(l: i32) -> usize {
+    2 * l.unsigned_abs() as usize + (l < 0) as usize
+}
+
+struct Clause {
+    lits: Vec<i32>,
+    active: bool,
+    core: bool,
+    // lemmas whose addition has been undone by the backward pass never become active again
+    dead: bool,
+}
+
+/// Hint recorded while checking, in terms of internal clause indices.
+#[derive(Clone, Copy)]
+enum Hint {
+    Unit(usize),
+    Rat(usize),
+}
+
+/// Backward DRAT checker. Every clause of the formula and the proof lives in one database and
+/// is switched on and off as the checker moves through the proof.
+struct Checker {
+    clauses: Vec<Clause>,
+    watches: Vec<Vec<usize>>,
+    units: Vec<usize>,
+
+    assigns: Vec<i8>,
+    reason: Vec<Option<usize>>,
+    seen: Vec<bool>,
+    trail: Vec<i32>,
+}
+
+impl Checker {
+    fn new(num_vars: usize) -> Checker {
+        Checker {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * num_vars + 2],
+            units: Vec::new(),
+
+            assigns: vec![0; num_vars + 1],
+            reason: vec![None; num_vars + 1],
+            seen: vec![false; num_vars + 1],
+            trail: Vec::new(),
+        }
+    }
+
+    fn value(&self, l: i32) -> i8 {
+        let v = self.assigns[l.unsigned_abs() as usize];
+
+        if l < 0 {
+            -v
+        } else {
+            v
+        }
+    }
+
+    fn assign(&mut self, l: i32, reason: Option<usize>) {
+        let v = l.unsigned_abs() as usize;
+
+        self.assigns[v] = if l < 0 { -1 } else { 1 };
+        self.reason[v] = reason;
+        self.trail.push(l);
+    }
+
+    fn push(&mut self, mut lits: Vec<i32>) -> usize {
+        let c = self.clauses.len();
+
+        lits.sort_unstable();
+        lits.dedup();
+
+        match lits.len() {
+            0 | 1 => self.units.push(c),
+            _ => {
+                self.watches[index(lits[0])].push(c);
+                self.watches[index(lits[1])].push(c);
+            }
+        }
+
+        self.clauses.push(Clause {
+            lits,
+            active: true,
+            core: false,
+            dead: false,
+        });
+
+        c
+    }
+
+    fn reset(&mut self) {
+        for l in self.trail.drain(..) {
+            let v = l.unsigned_abs() as usize;
+
+            self.assigns[v] = 0;
+            self.reason[v] = None;
+        }
+    }
+
+    /// Visit the clauses watching the complement of `l`, considering only core or only non-core
+    /// clauses. Returns a falsified clause.
+    fn visit(&mut self, l: i32, core: bool) -> Option<usize> {
+        let false_lit = -l;
+        let mut watches = std::mem::take(&mut self.watches[index(false_lit)]);
+        let mut conflict = None;
+        let mut i = 0;
+
+        while i < watches.len() {
+            let c = watches[i];
+
+            if self.clauses[c].dead {
+                watches.swap_remove(i);
+                continue;
+            }
+
+            if !self.clauses[c].active || self.clauses[c].core != core || conflict.is_some() {
+                i += 1;
+                continue;
+            }
+
+            if self.clauses[c].lits[0] == false_lit {
+                self.clauses[c].lits.swap(0, 1);
+            }
+
+            let first = self.clauses[c].lits[0];
+
+            if self.value(first) == 1 {
+                i += 1;
+                continue;
+            }
+
+            let replacement = (2..self.clauses[c].lits.len())
+                .find(|k| self.value(self.clauses[c].lits[*k]) != -1);
+
+            if let Some(k) = replacement {
+                self.clauses[c].lits.swap(1, k);
+
+                let other = self.clauses[c].lits[1];
+                self.watches[index(other)].push(c);
+                watches.swap_remove(i);
+                continue;
+            }
+
+            if self.value(first) == -1 {
+                conflict = Some(c);
+            } else {
+                self.assign(first, Some(c));
+            }
+
+            i += 1;
+        }
+
+        self.watches[index(false_lit)] = watches;
+
+        conflict
+    }
+
+    /// Unit propagation preferring core clauses: non-core clauses are only used once core
+    /// clauses are exhausted, and only for one literal at a time.
+    fn propagate(&mut self) -> Option<usize> {
+        let mut core_head = 0;
+        let mut other_head = 0;
+
+        loop {
+            while core_head < self.trail.len() {
+                let l = self.trail[core_head];
+                core_head += 1;
+
+                if let Some(conflict) = self.visit(l, true) {
+                    return Some(conflict);
+                }
+            }
+
+            if other_head == self.trail.len() {
+                return None;
+            }
+
+            let l = self.trail[other_head];
+            other_head += 1;
+
+            if let Some(conflict) = self.visit(l, false) {
+                return Some(conflict);
+            }
+        }
+    }
+
+    /// Check that unit propagation refutes the given literals together with the active clauses.
+    /// On success the clauses involved become core and are returned in propagation order, the
+    /// falsified clause last.
+    fn refute(&mut self, assumptions: &[i32]) -> Option<Vec<usize>> {
+        let mut conflict = None;
+
+        for a in assumptions {
+            match self.value(*a) {
+                0 => self.assign(*a, None),
+                1 => {}
+                // complementary assumptions refute themselves
+                _ => {
+                    self.reset();
+                    return Some(vec![]);
+                }
+            }
+        }
+
+        for k in 0..self.units.len() {
+            let u = self.units[k];
+
+            if !self.clauses[u].active {
+                continue;
+            }
+
+            let value = match self.clauses[u].lits.first() {
+                Some(l) => self.value(*l),
+                None => -1,
+            };
+
+            match value {
+                0 => self.assign(self.clauses[u].lits[0], Some(u)),
+                -1 => {
+                    conflict = Some(u);
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let conflict = match conflict.or_else(|| self.propagate()) {
+            Some(conflict) => conflict,
+            None => {
+                self.reset();
+                return None;
+            }
+        };
+
+        // walk the trail backwards collecting the reasons the conflict depends on
+        let mut used = vec![conflict];
+
+        for l in &self.clauses[conflict].lits {
+            self.seen[l.unsigned_abs() as usize] = true;
+        }
+
+        for k in (0..self.trail.len()).rev() {
+            let v = self.trail[k].unsigned_abs() as usize;
+
+            if !self.seen[v] {
+                continue;
+            }
+
+            self.seen[v] = false;
+
+            if let Some(r) = self.reason[v] {
+                used.push(r);
+
+                // the reason contains the literal it propagated, which is done with
+                for l in &self.clauses[r].lits {
+                    let u = l.unsigned_abs() as usize;
+
+                    if u != v {
+                        self.seen[u] = true;
+                    }
+                }
+            }
+        }
+
+        used.reverse();
+
+        for c in &used {
+            self.clauses[*c].core = true;
+        }
+
+        self.reset();
+
+        Some(used)
+    }
+
+    /// Check a lemma against the active clauses, first as RUP and then as RAT on its first
+    /// literal.
+    fn check(&mut self, lemma: &[i32]) -> Option<Vec<Hint>> {
+        let negated: Vec<i32> = lemma.iter().map(|l| -l).collect();
+
+        if let Some(used) = self.refute(&negated) {
+            return Some(used.into_iter().map(Hint::Unit).collect());
+        }
+
+        let pivot = *lemma.first()?;
+        let mut hints = Vec::new();
+
+        let candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|c| self.clauses[*c].active && self.clauses[*c].lits.contains(&-pivot))
+            .collect();
+
+        for d in candidates {
+            let lits = &self.clauses[d].lits;
+
+            // resolvents that are tautologies need no refutation
+            if lits.iter().any(|l| *l != -pivot && lemma.contains(&-l)) {
+                continue;
+            }
+
+            let mut assumptions = negated.clone();
+            assumptions.extend(lits.iter().filter(|l| **l != -pivot).map(|l| -l));
+
+            let used = self.refute(&assumptions)?;
+
+            self.clauses[d].core = true;
+            hints.push(Hint::Rat(d));
+            hints.extend(used.into_iter().map(Hint::Unit));
+        }
+
+        Some(hints)
+    }
+}
+
+/// The literals of an added lemma in proof order, which matters for RAT, without duplicates.
+fn lemma(step: &Step) -> Vec<i32> {
+    let mut lemma = Vec::new();
+
+    if let Step::Add(clause) = step {
+        for l in clause {
+            if !lemma.contains(l) {
+                lemma.push(*l);
+            }
+        }
+    }
+
+    lemma
+}
+
+/// Check a DRAT refutation of `formula` by backward checking with core-first propagation. Only
+/// lemmas the refutation depends on are checked, and only those appear in the resulting LRAT
+/// proof. Deletions of unit clauses are ignored.
+pub fn check_drat(formula: &[Vec<i32>], proof: &[Step]) -> Result<Certificate, CheckError> {
+    let num_vars = formula
+        .iter()
+        .chain(proof.iter().map(|step| match step {
+            Step::Add(c) | Step::Delete(c) => c,
+        }))
+        .flatten()
+        .map(|l| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    let mut checker = Checker::new(num_vars);
+    let mut lookup: HashMap<Vec<i32>, Vec<usize>> = HashMap::new();
+
+    for clause in formula {
+        let c = checker.push(clause.clone());
+
+        lookup
+            .entry(checker.clauses[c].lits.clone())
+            .or_default()
+            .push(c);
+    }
+
+    // forward pass: build the database up to the first empty lemma, remembering which clause
+    // each step adds or deletes
+    let mut events: Vec<(usize, bool, usize)> = Vec::new();
+
+    for (k, step) in proof.iter().enumerate() {
+        match step {
+            Step::Add(lemma) if lemma.is_empty() => break,
+
+            Step::Add(lemma) => {
+                let c = checker.push(lemma.clone());
+
+                lookup
+                    .entry(checker.clauses[c].lits.clone())
+                    .or_default()
+                    .push(c);
+
+                events.push((k, true, c));
+            }
+
+            Step::Delete(clause) => {
+                let mut key = clause.clone();
+
+                key.sort_unstable();
+                key.dedup();
+
+                if key.len() <= 1 {
+                    continue;
+                }
+
+                if let Some(c) = lookup.get_mut(&key).and_then(|cs| cs.pop()) {
+                    checker.clauses[c].active = false;
+                    events.push((k, false, c));
+                }
+            }
+        }
+    }
+
+    let used = checker.refute(&[]).ok_or(CheckError::NoConflict)?;
+    let mut checked: HashMap<usize, Vec<Hint>> = HashMap::new();
+
+    // backward pass: undo each step, checking lemmas that became part of the core
+    for (e, (k, added, c)) in events.iter().enumerate().rev() {
+        checker.clauses[*c].active = !added;
+        checker.clauses[*c].dead = *added;
+
+        if *added && checker.clauses[*c].core {
+            let lemma = lemma(&proof[*k]);
+            let hints = checker.check(&lemma).ok_or(CheckError::Lemma(*k))?;
+
+            checked.insert(e, hints);
+        }
+    }
+
+    // number the formula clauses from one in order, followed by the checked lemmas, and mirror
+    // the deletions so the LRAT proof sees the same clauses as the DRAT check did
+    let mut ids = vec![0; checker.clauses.len()];
+
+    for (c, id) in ids.iter_mut().enumerate().take(formula.len()) {
+        *id = c as u64 + 1;
+    }
+
+    let translate = |ids: &[u64], hints: &[Hint]| -> Vec<i64> {
+        hints
+            .iter()
+            .map(|hint| match hint {
+                Hint::Unit(c) => ids[*c] as i64,
+                Hint::Rat(c) => -(ids[*c] as i64),
+            })
+            .collect()
+    };
+
+    let mut next = formula.len() as u64 + 1;
+    let mut lrat = Vec::new();
+
+    for (e, (k, added, c)) in events.iter().enumerate() {
+        if *added {
+            if let Some(hints) = checked.remove(&e) {
+                ids[*c] = next;
+
+                lrat.push(Lrat::Add {
+                    id: next,
+                    clause: lemma(&proof[*k]),
+                    hints: translate(&ids, &hints),
+                });
+
+                next += 1;
+            }
+        } else if ids[*c] != 0 {
+            lrat.push(Lrat::Delete {
+                id: next - 1,
+                ids: vec![ids[*c]],
+            });
+        }
+    }
+
+    lrat.push(Lrat::Add {
+        id: next,
+        clause: vec![],
+        hints: translate(&ids, &used.into_iter().map(Hint::Unit).collect::<Vec<_>>()),
+    });
+
+    let core = (0..formula.len())
+        .filter(|c| checker.clauses[*c].core)
+        .collect();
+
+    Ok(Certificate { core, lrat })
+}
+
+/// Check an LRAT refutation of `formula`, whose clauses have ids starting from one. Each step
+/// is verified directly from its hints without any search.
+pub fn check_lrat(formula: &[Vec<i32>], proof: &[Lrat]) -> Result<(), CheckError> {
+    let mut clauses: HashMap<u64, Vec<i32>> = formula
+        .iter()
+        .enumerate()
+        .map(|(k, clause)| (k as u64 + 1, clause.clone()))
+        .collect();
+
+    for step in proof {
+        let (id, clause, hints) = match step {
+            Lrat::Delete { ids, .. } => {
+                for i in ids {
+                    clauses.remove(i);
+                }
+
+                continue;
+            }
+
+            Lrat::Add { id, clause, hints } => (*id, clause, hints),
+        };
+
+        let mut assignment: HashMap<i32, bool> = HashMap::new();
+
+        for l in clause {
+            assignment.insert(l.abs(), *l < 0);
+        }
+
+        // the hints before the first negative one are shared by all RAT candidates
+        let split = hints.iter().position(|h| *h < 0).unwrap_or(hints.len());
+
+        let verified = match unit_chain(&clauses, &mut assignment, &hints[..split]) {
+            None => false,
+            Some(true) => true,
+            Some(false) if split == hints.len() => false,
+            Some(false) => rat(&clauses, &assignment, clause, &hints[split..]),
+        };
+
+        if !verified {
+            return Err(CheckError::Hint(id));
+        }
+
+        if clause.is_empty() {
+            return Ok(());
+        }
+
+        clauses.insert(id, clause.clone());
+    }
+
+    Err(CheckError::NoConflict)
+}
+
+/// Follow a chain of hints, each of which must become unit or falsified under the assignment.
+/// Returns whether a conflict was reached, or `None` if a hint is invalid.
+fn unit_chain(
+    clauses: &HashMap<u64, Vec<i32>>,
+    assignment: &mut HashMap<i32, bool>,
+    hints: &[i64],
+) -> Option<bool> {
+    for hint in hints {
+        let clause = clauses.get(&(*hint as u64))?;
+        let mut unassigned = None;
+
+        for l in clause {
+            match assignment.get(&l.abs()) {
+                Some(v) if *v == (*l > 0) => return None,
+                Some(_) => {}
+                None if unassigned.is_some() && unassigned != Some(*l) => return None,
+                None => unassigned = Some(*l),
+            }
+        }
+
+        match unassigned {
+            None => return Some(true),
+            Some(l) => {
+                assignment.insert(l.abs(), l > 0);
+            }
+        }
+    }
+
+    Some(false)
+}
+
+/// Check the RAT part of an LRAT step: every clause containing the complement of the pivot must
+/// either be satisfied by the assignment or be refuted by its group of hints.
+fn rat(
+    clauses: &HashMap<u64, Vec<i32>>,
+    assignment: &HashMap<i32, bool>,
+    clause: &[i32],
+    hints: &[i64],
+) -> bool {
+    let pivot = match clause.first() {
+        Some(pivot) => *pivot,
+        None => return false,
+    };
+
+    let mut groups: HashMap<u64, &[i64]> = HashMap::new();
+    let mut rest = hints;
+
+    while let Some((head, tail)) = rest.split_first() {
+        let end = tail.iter().position(|h| *h < 0).unwrap_or(tail.len());
+
+        groups.insert(head.unsigned_abs(), &tail[..end]);
+        rest = &tail[end..];
+    }
+
+    clauses
+        .iter()
+        .filter(|(_, d)| d.contains(&-pivot))
+        .all(|(id, d)| {
+            let mut assignment = assignment.clone();
+
+            for l in d.iter().filter(|l| **l != -pivot) {
+                match assignment.get(&l.abs()) {
+                    Some(v) if *v == (*l > 0) => return true,
+                    _ => {
+                        assignment.insert(l.abs(), *l < 0);
+                    }
+                }
+            }
+
+            match groups.get(id) {
+                Some(group) => unit_chain(clauses, &mut assignment, group) == Some(true),
+                None => false,
+            }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dp::DavisPutnam;
+    use crate::drat::Drat;
+    use crate::solver::Solver;
+    use crate::testing::pigeonhole;
+    use crate::Status;
+    use std::sync::{Arc, Mutex};
+
+    /// Shared buffer to capture proofs written through a `Drat`.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn solver_proof(formula: &[Vec<i32>], binary: bool) -> Vec<Step> {
+        let buffer = Buffer::default();
+        let mut solver = Solver::new();
+
+        solver.set_proof(Drat::new(buffer.clone(), binary));
+
+        for clause in formula {
+            solver.add_clause(clause);
+        }
+
+        assert_eq!(solver.solve(), Status::Unsat);
+        solver.take_proof().unwrap().finish().unwrap();
+
+        let bytes = buffer.0.lock().unwrap().clone();
+
+        read_drat(&bytes[..]).unwrap()
+    }
+
+    #[test]
+    fn rup_01() {
+        let formula = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2], vec![3]];
+        let proof = read_drat("2 0\n0\n".as_bytes()).unwrap();
+
+        let certificate = check_drat(&formula, &proof).unwrap();
+
+        assert_eq!(certificate.core, vec![0, 1, 2, 3]);
+        assert_eq!(check_lrat(&formula, &certificate.lrat), Ok(()));
+    }
+
+    #[test]
+    fn rup_02() {
+        let formula = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+        let proof = read_drat("3 0\n-3 0\n0\n".as_bytes()).unwrap();
+
+        assert_eq!(
+            check_drat(&formula, &proof).unwrap_err(),
+            CheckError::Lemma(1)
+        );
+
+        let proof = read_drat("2 0\n".as_bytes()).unwrap();
+
+        assert!(check_drat(&formula, &proof).is_ok());
+        assert_eq!(
+            check_drat(&formula, &[]).unwrap_err(),
+            CheckError::NoConflict
+        );
+    }
+
+    #[test]
+    fn rup_03() {
+        let mut checker = Checker::new(4);
+
+        checker.push(vec![1, 2]);
+        checker.push(vec![-2, 3]);
+        checker.push(vec![-3, 1]);
+        checker.push(vec![-4, 2]);
+
+        // the chain 2, 3 leaves no marks behind to drag the clause of 4 into the second
+        let mut first = checker.refute(&[-1]).unwrap();
+
+        assert!(checker.seen.iter().all(|seen| !seen));
+
+        let mut second = checker.refute(&[-1, 4]).unwrap();
+
+        first.sort_unstable();
+        second.sort_unstable();
+
+        assert_eq!(first, vec![0, 1, 2]);
+        assert_eq!(second, vec![0, 1, 2]);
+        assert!(!checker.clauses[3].core);
+    }
+
+    #[test]
+    fn rat_01() {
+        // 3 is fresh, so defining 3 <-> 1 is RAT on 3
+        let formula = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+        let proof = read_drat("3 -1 0\n-3 1 0\n2 0\n0\n".as_bytes()).unwrap();
+
+        assert!(check_drat(&formula, &proof).is_ok());
+    }
+
+    #[test]
+    fn solver_01() {
+        let formula = pigeonhole(5);
+
+        for binary in [false, true] {
+            let proof = solver_proof(&formula, binary);
+            let certificate = check_drat(&formula, &proof).unwrap();
+
+            assert_eq!(check_lrat(&formula, &certificate.lrat), Ok(()));
+
+            let mut text = Vec::new();
+            write_lrat(&mut text, &certificate.lrat).unwrap();
+
+            assert_eq!(read_lrat(&text[..]).unwrap(), certificate.lrat);
+        }
+    }
+
+    #[test]
+    fn dp_01() {
+        let formula = pigeonhole(3);
+        let buffer = Buffer::default();
+        let mut dp = DavisPutnam::new(formula.clone());
+
+        dp.set_proof(Drat::new(buffer.clone(), false));
+
+        assert_eq!(dp.solve(), Status::Unsat);
+        dp.take_proof().unwrap().finish().unwrap();
+
+        let proof = read_drat(&buffer.0.lock().unwrap()[..]).unwrap();
+
+        assert!(check_drat(&formula, &proof).is_ok());
+    }
+
+    #[test]
+    fn lrat_01() {
+        let formula = vec![vec![1, 2], vec![-1, 2], vec![1, -2], vec![-1, -2]];
+        let good = read_lrat("5 2 0 1 2 0\n6 0 5 3 4 0\n".as_bytes()).unwrap();
+        let bad = read_lrat("5 2 0 1 0\n6 0 5 3 4 0\n".as_bytes()).unwrap();
+
+        assert_eq!(check_lrat(&formula, &good), Ok(()));
+        assert_eq!(check_lrat(&formula, &bad), Err(CheckError::Hint(5)));
+    }
+}
@@ -0,0 +1,640 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::dimacs::Quantifier;
+use crate::dp::conflicts;
+use crate::solver::Solver;
+use crate::terminator::Terminator;
+use crate::Status;
+
+/// Blocks of quantified variables, outermost first.
+type Prefix = Vec<(Quantifier, Vec<i32>)>;
+
+/// Decision procedure for quantified boolean formulas in prenex CNF. Formulas are first solved by
+/// expansion: universal reduction, resolution on the innermost existential variables and
+/// expansion of the innermost universal variables, until a propositional formula is left for
+/// the SAT solver. If the formula grows past a limit, counterexample guided abstraction
+/// refinement is used instead, which expands universals only for the counter moves it finds.
+pub struct Qbf {
+    num_vars: usize,
+    prefix: Prefix,
+    clauses: Vec<Vec<i32>>,
+    limit: usize,
+    terminator: Option<Terminator>,
+    model: Vec<i32>,
+}
+
+impl Qbf {
+    /// Create a formula from a prefix, as read by `Dimacs::prefix`, and clauses. Variables
+    /// missing from the prefix are existentially quantified in the outermost block.
+    pub fn new(prefix: &[(Quantifier, Vec<i32>)], mut clauses: Vec<Vec<i32>>) -> Qbf {
+        // tautologies are true under every assignment, and would break resolution
+        clauses.retain(|c| !c.iter().any(|l| c.contains(&-l)));
+
+        let quantified: HashSet<i32> = prefix.iter().flat_map(|(_, vars)| vars).copied().collect();
+
+        let mut free: Vec<i32> = clauses
+            .iter()
+            .flatten()
+            .map(|l| l.abs())
+            .filter(|v| !quantified.contains(v))
+            .collect();
+
+        free.sort_unstable();
+        free.dedup();
+
+        let num_vars = quantified
+            .iter()
+            .chain(free.iter())
+            .map(|v| *v as usize)
+            .max()
+            .unwrap_or(0);
+
+        let mut blocks = vec![(Quantifier::Exists, free)];
+        blocks.extend(prefix.iter().cloned());
+
+        let limit = 10 * clauses.len() + 10000;
+
+        Qbf {
+            num_vars,
+            prefix: normalize(blocks),
+            clauses,
+            limit,
+            terminator: None,
+            model: Vec::new(),
+        }
+    }
+
+    /// The number of clauses expansion may grow the formula to before falling back to
+    /// abstraction refinement.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops, its conflict limit
+    /// applying to each call of the SAT solver.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Decide the formula: `Status::Sat` if it is true and `Status::Unsat` if it is false.
+    pub fn solve(&mut self) -> Status {
+        self.model.clear();
+
+        match self.expand() {
+            Some(status) => status,
+            None => self.cegar(),
+        }
+    }
+
+    /// The assignment to the outermost existential variables found to make a true formula
+    /// true, as one literal per variable.
+    pub fn model(&self) -> Vec<i32> {
+        self.model.clone()
+    }
+
+    /// The variables of the outermost block, if it is existential.
+    fn outermost(&self) -> &[i32] {
+        match self.prefix.first() {
+            Some((Quantifier::Exists, vars)) => vars,
+            _ => &[],
+        }
+    }
+
+    /// Solve by expansion, giving up once the formula grows past the limit.
+    fn expand(&mut self) -> Option<Status> {
+        let mut prefix = self.prefix.clone();
+        let mut clauses = self.clauses.clone();
+        let mut num_vars = self.num_vars;
+
+        loop {
+            if self.terminator.as_ref().is_some_and(|t| t.should_stop(0)) {
+                return Some(Status::Unknown);
+            }
+
+            reduce(&prefix, &mut clauses);
+            prefix = normalize(prefix);
+
+            if clauses.iter().any(|c| c.is_empty()) {
+                return Some(Status::Unsat);
+            }
+
+            if clauses.len() > self.limit {
+                return None;
+            }
+
+            let (quantifier, block) = match prefix.last() {
+                None => return Some(Status::Sat),
+                Some((quantifier, block)) => (*quantifier, block.clone()),
+            };
+
+            // an innermost universal block has been reduced away
+            if quantifier == Quantifier::Forall {
+                prefix.pop();
+                continue;
+            }
+
+            if prefix.len() == 1 {
+                let mut solver = solver(self.terminator.as_ref());
+
+                for clause in &clauses {
+                    solver.add_clause(clause);
+                }
+
+                let status = solver.solve();
+
+                if status == Status::Sat {
+                    self.model = project(&solver, self.outermost());
+                }
+
+                return Some(status);
+            }
+
+            let occurrences = |l: i32| clauses.iter().filter(|c| c.contains(&l)).count() as isize;
+
+            let (pivot, growth) = block
+                .iter()
+                .map(|v| {
+                    let (pos, neg) = (occurrences(*v), occurrences(-v));
+                    (*v, pos * neg - pos - neg)
+                })
+                .min_by_key(|(_, growth)| *growth)
+                .unwrap();
+
+            // expanding a universal copies every clause with an innermost variable
+            let inner: HashSet<i32> = block.iter().copied().collect();
+            let copied = clauses
+                .iter()
+                .filter(|c| c.iter().any(|l| inner.contains(&l.abs())))
+                .count() as isize;
+
+            let last = prefix.len() - 1;
+
+            if growth <= copied {
+                conflicts(&mut clauses, pivot, None);
+                prefix[last].1.retain(|v| *v != pivot);
+            } else {
+                let u = prefix[last - 1].1.pop().unwrap();
+                let copies = expand(&mut clauses, u, &block, &mut num_vars);
+
+                prefix[last].1.extend(copies);
+            }
+        }
+    }
+
+    /// Solve by abstraction refinement.
+    fn cegar(&mut self) -> Status {
+        let mut num_vars = self.num_vars;
+        let terminator = self.terminator.as_ref();
+
+        match exists(
+            &self.prefix,
+            &self.clauses,
+            false,
+            &mut num_vars,
+            terminator,
+        ) {
+            Err(status) => status,
+
+            Ok(assignment) => {
+                let outermost: HashSet<i32> = self.outermost().iter().copied().collect();
+
+                self.model = assignment
+                    .into_iter()
+                    .filter(|l| outermost.contains(&l.abs()))
+                    .collect();
+
+                Status::Sat
+            }
+        }
+    }
+}
+
+/// A SAT solver stopped by the terminator, if any.
+fn solver(terminator: Option<&Terminator>) -> Solver {
+    let mut solver = Solver::new();
+
+    if let Some(terminator) = terminator {
+        solver.set_terminator(terminator.clone());
+    }
+
+    solver
+}
+
+/// Drop empty blocks and merge adjacent blocks with the same quantifier.
+fn normalize(prefix: Prefix) -> Prefix {
+    let mut merged: Prefix = Vec::new();
+
+    for (quantifier, vars) in prefix {
+        if vars.is_empty() {
+            continue;
+        }
+
+        match merged.last_mut() {
+            Some((q, block)) if *q == quantifier => block.extend(vars),
+            _ => merged.push((quantifier, vars)),
+        }
+    }
+
+    merged
+}
+
+/// Universal reduction: drop universal literals quantified inside every existential literal of
+/// their clause.
+fn reduce(prefix: &[(Quantifier, Vec<i32>)], clauses: &mut [Vec<i32>]) {
+    let mut levels = HashMap::new();
+
+    for (depth, (quantifier, vars)) in prefix.iter().enumerate() {
+        for v in vars {
+            levels.insert(*v, (depth, *quantifier));
+        }
+    }
+
+    for clause in clauses.iter_mut() {
+        let deepest = clause
+            .iter()
+            .filter(|l| levels[&l.abs()].1 == Quantifier::Exists)
+            .map(|l| levels[&l.abs()].0)
+            .max();
+
+        clause.retain(|l| {
+            let (depth, quantifier) = levels[&l.abs()];
+            quantifier == Quantifier::Exists || Some(depth) < deepest
+        });
+    }
+}
+
+/// Replace the universal `u`, which is quantified just outside the innermost existential block
+/// `inner`, by both of its values. The innermost variables are renamed in the copy for `u`
+/// true, and the new names are returned.
+fn expand(clauses: &mut Vec<Vec<i32>>, u: i32, inner: &[i32], num_vars: &mut usize) -> Vec<i32> {
+    let rename: HashMap<i32, i32> = inner
+        .iter()
+        .map(|v| {
+            *num_vars += 1;
+            (*v, *num_vars as i32)
+        })
+        .collect();
+
+    let mut expanded = Vec::with_capacity(clauses.len());
+
+    for clause in clauses.drain(..) {
+        if !clause.iter().any(|l| rename.contains_key(&l.abs())) {
+            if !(clause.contains(&u) && clause.contains(&-u)) {
+                expanded.push(clause.into_iter().filter(|l| l.abs() != u).collect());
+            }
+
+            continue;
+        }
+
+        if !clause.contains(&-u) {
+            expanded.push(clause.iter().filter(|l| **l != u).copied().collect());
+        }
+
+        if !clause.contains(&u) {
+            expanded.push(
+                clause
+                    .iter()
+                    .filter(|l| **l != -u)
+                    .map(|l| match rename.get(&l.abs()) {
+                        Some(v) => v * l.signum(),
+                        None => *l,
+                    })
+                    .collect(),
+            );
+        }
+    }
+
+    *clauses = expanded;
+
+    rename.values().copied().collect()
+}
+
+/// The value of each variable in the last model of the solver, false if it was never used.
+fn project(solver: &Solver, vars: &[i32]) -> Vec<i32> {
+    vars.iter()
+        .map(|v| {
+            if solver.value(*v) == Some(true) {
+                *v
+            } else {
+                -v
+            }
+        })
+        .collect()
+}
+
+/// Simplify the clauses under a partial assignment.
+fn assign(clauses: &[Vec<i32>], assignment: &[i32]) -> Vec<Vec<i32>> {
+    let assignment: HashSet<i32> = assignment.iter().copied().collect();
+
+    clauses
+        .iter()
+        .filter(|c| !c.iter().any(|l| assignment.contains(l)))
+        .map(|c| {
+            c.iter()
+                .filter(|l| !assignment.contains(&-**l))
+                .copied()
+                .collect()
+        })
+        .collect()
+}
+
+/// Flip the quantifiers of a prefix.
+fn flip(prefix: &[(Quantifier, Vec<i32>)]) -> Prefix {
+    prefix
+        .iter()
+        .map(|(quantifier, vars)| match quantifier {
+            Quantifier::Exists => (Quantifier::Forall, vars.clone()),
+            Quantifier::Forall => (Quantifier::Exists, vars.clone()),
+        })
+        .collect()
+}
+
+/// Find an assignment to the outermost block that wins the formula for the existential player,
+/// treating a formula starting with a universal block as having an empty outermost block. The
+/// matrix is the conjunction of the clauses, or its negation if `negated` is set.
+///
+/// For `∃X ∀Y Ψ`, candidates for `X` are taken from an abstraction holding copies of `Ψ` for the
+/// counter moves of `Y` found so far, and counter moves are found by solving the negation of `Ψ`
+/// under each candidate. Copies of a negated matrix are conjoined with a selector per clause,
+/// one of which must be true and each of which falsifies its clause, quantified innermost.
+///
+/// Fails with `Status::Unsat` if there is no such assignment, or with `Status::Unknown` once the
+/// terminator stops.
+fn exists(
+    prefix: &[(Quantifier, Vec<i32>)],
+    clauses: &[Vec<i32>],
+    negated: bool,
+    num_vars: &mut usize,
+    terminator: Option<&Terminator>,
+) -> Result<Vec<i32>, Status> {
+    let (x, inner): (&[i32], _) = match prefix.first() {
+        Some((Quantifier::Exists, vars)) => (vars, &prefix[1..]),
+        _ => (&[], prefix),
+    };
+
+    if inner.is_empty() && negated {
+        // falsify any clause that is not a tautology
+        let clause = clauses
+            .iter()
+            .find(|c| !c.iter().any(|l| c.contains(&-l)))
+            .ok_or(Status::Unsat)?;
+
+        return Ok(x
+            .iter()
+            .map(|v| if clause.contains(&-v) { *v } else { -v })
+            .collect());
+    }
+
+    if inner.is_empty() {
+        let mut solver = solver(terminator);
+
+        for clause in clauses {
+            solver.add_clause(clause);
+        }
+
+        return match solver.solve() {
+            Status::Sat => Ok(project(&solver, x)),
+            status => Err(status),
+        };
+    }
+
+    let rest = &inner[1..];
+    let own: HashSet<i32> = x.iter().copied().collect();
+
+    // the abstraction quantifies copies of the variables of `rest` at the same depth
+    let mut abstraction: Prefix = vec![(Quantifier::Exists, x.to_vec())];
+    abstraction.extend(rest.iter().skip(1).map(|(q, _)| (*q, vec![])));
+
+    if negated && abstraction.last().unwrap().0 == Quantifier::Forall {
+        abstraction.push((Quantifier::Exists, vec![]));
+    }
+
+    let mut refinements: Vec<Vec<i32>> = Vec::new();
+
+    loop {
+        let candidate: Vec<i32> = exists(
+            &normalize(abstraction.clone()),
+            &refinements,
+            false,
+            num_vars,
+            terminator,
+        )?
+        .into_iter()
+        .filter(|l| own.contains(&l.abs()))
+        .collect();
+
+        let counter = match exists(
+            &flip(inner),
+            &assign(clauses, &candidate),
+            !negated,
+            num_vars,
+            terminator,
+        ) {
+            Err(Status::Unsat) => return Ok(candidate),
+            Err(status) => return Err(status),
+            Ok(counter) => counter,
+        };
+
+        let mut rename = HashMap::new();
+
+        for (depth, (_, vars)) in rest.iter().enumerate() {
+            for v in vars {
+                *num_vars += 1;
+                rename.insert(*v, *num_vars as i32);
+                abstraction[depth].1.push(*num_vars as i32);
+            }
+        }
+
+        let copy = assign(clauses, &counter).into_iter().map(|clause| {
+            clause
+                .iter()
+                .map(|l| match rename.get(&l.abs()) {
+                    Some(v) => v * l.signum(),
+                    None => *l,
+                })
+                .collect::<Vec<i32>>()
+        });
+
+        if !negated {
+            refinements.extend(copy);
+            continue;
+        }
+
+        let mut selectors = Vec::new();
+
+        for clause in copy {
+            *num_vars += 1;
+
+            let s = *num_vars as i32;
+
+            for l in clause {
+                refinements.push(vec![-s, -l]);
+            }
+
+            selectors.push(s);
+        }
+
+        refinements.push(selectors.clone());
+        abstraction.last_mut().unwrap().1.extend(selectors);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs::Dimacs;
+    use crate::encoder::Encoder;
+    use crate::rng::Rng;
+    use crate::testing::random_3sat;
+    use crate::words::{Bits, Word};
+
+    /// Evaluate a formula by recursing over its prefix.
+    fn brute_force(prefix: &[(Quantifier, i32)], clauses: &[Vec<i32>]) -> bool {
+        match prefix.split_first() {
+            None => clauses.iter().all(|c| !c.is_empty()),
+
+            Some(((quantifier, v), rest)) => {
+                let mut branch = |l: i32| brute_force(rest, &assign(clauses, &[l]));
+
+                match quantifier {
+                    Quantifier::Exists => branch(*v) || branch(-v),
+                    Quantifier::Forall => branch(*v) && branch(-v),
+                }
+            }
+        }
+    }
+
+    fn solve(prefix: &[(Quantifier, Vec<i32>)], clauses: &[Vec<i32>], limit: usize) -> Status {
+        let mut qbf = Qbf::new(prefix, clauses.to_vec());
+
+        qbf.set_limit(limit);
+
+        let status = qbf.solve();
+
+        // the outermost existential moves must win against every universal response
+        if status == Status::Sat && !qbf.outermost().is_empty() {
+            let model = qbf.model();
+            let vars: Vec<(Quantifier, i32)> = qbf.prefix[1..]
+                .iter()
+                .flat_map(|(q, vars)| vars.iter().map(move |v| (*q, *v)))
+                .collect();
+
+            assert!(brute_force(&vars, &assign(clauses, &model)));
+        }
+
+        status
+    }
+
+    #[test]
+    fn qdimacs_01() {
+        let text = "p cnf 3 2
+        e 1 0
+        a 2 0
+        e 3 0
+        1 -2 3 0
+        -1 2 -3 0
+        ";
+
+        let mut dimacs = Dimacs::new(text.as_bytes());
+        let clauses: Vec<Vec<i32>> = dimacs.by_ref().collect();
+
+        for limit in [0, 1000] {
+            assert_eq!(solve(dimacs.prefix(), &clauses, limit), Status::Sat);
+        }
+
+        // x2 cannot be matched by x1 once x1 is chosen first
+        let prefix = vec![(Quantifier::Exists, vec![1]), (Quantifier::Forall, vec![2])];
+        let clauses = vec![vec![1, -2], vec![-1, 2]];
+
+        for limit in [0, 1000] {
+            assert_eq!(solve(&prefix, &clauses, limit), Status::Unsat);
+        }
+    }
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(5);
+
+        for round in 0..100 {
+            // five blocks of two variables with alternating quantifiers
+            let first = if rng.next_bool() {
+                Quantifier::Exists
+            } else {
+                Quantifier::Forall
+            };
+
+            let prefix: Prefix = (0..5)
+                .map(|b| {
+                    let quantifier = match (first, b % 2) {
+                        (q, 0) => q,
+                        (Quantifier::Exists, _) => Quantifier::Forall,
+                        (Quantifier::Forall, _) => Quantifier::Exists,
+                    };
+
+                    (quantifier, vec![2 * b + 1, 2 * b + 2])
+                })
+                .collect();
+
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 10, 7);
+
+            let order: Vec<(Quantifier, i32)> = prefix
+                .iter()
+                .flat_map(|(q, vars)| vars.iter().map(move |v| (*q, *v)))
+                .collect();
+
+            let expected = if brute_force(&order, &clauses) {
+                Status::Sat
+            } else {
+                Status::Unsat
+            };
+
+            for limit in [0, 40, 100000] {
+                assert_eq!(
+                    solve(&prefix, &clauses, limit),
+                    expected,
+                    "round {} limit {}",
+                    round,
+                    limit
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn word_01() {
+        let bits = Bits::new();
+        let k = Word::var(&bits, 4);
+        let x = Word::var(&bits, 4);
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let ks = encoder.word(&k);
+        let xs = encoder.word(&x);
+
+        // there is a mask k with x | k = k for every x
+        let covered = (&(&x | &k) ^ &k).any();
+        let l = encoder.lit(covered.ids()[0]);
+
+        let mut clauses = encoder.take_clauses();
+        clauses.push(vec![-l]);
+
+        // the gates of the circuit are functions of k and x
+        let gates: Vec<i32> = (1..=encoder.num_vars() as i32)
+            .filter(|v| !ks.contains(v) && !xs.contains(v))
+            .collect();
+
+        let prefix = vec![
+            (Quantifier::Exists, ks.clone()),
+            (Quantifier::Forall, xs),
+            (Quantifier::Exists, gates),
+        ];
+
+        for limit in [0, 100000] {
+            let mut qbf = Qbf::new(&prefix, clauses.clone());
+
+            qbf.set_limit(limit);
+
+            assert_eq!(qbf.solve(), Status::Sat);
+            assert_eq!(qbf.model(), ks);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::approx::ApproxCounter;
+use crate::encoder::Encoder;
+use crate::rng::Rng;
+use crate::terminator::Terminator;
+use crate::words::Word;
+use crate::Status;
+
+/// Tolerance of the cell sizes, as in UniGen.
+const KAPPA: f64 = 0.638;
+
+/// How many times hashes are drawn anew for a sample before giving up.
+const RETRIES: usize = 10;
+
+/// What sampling needs to know about the models, found on the first sample.
+enum Plan {
+    Unsat,
+    Exact(Vec<Vec<i32>>),
+    Hashed(usize),
+}
+
+/// Near-uniform sampler of models projected onto a sampling set, in the style of UniGen. Random
+/// XOR constraints over the sampling set split the projected models into cells, and a sample is
+/// drawn uniformly from a cell of a size that makes every projected model about equally likely.
+/// The number of constraints is chosen from an approximate count, made before the first
+/// sample. When there are few projected models, they are all enumerated instead and sampled
+/// exactly uniformly.
+///
+/// Samples are reproducible from the seed.
+pub struct Sampler {
+    counter: ApproxCounter,
+    rng: Rng,
+    plan: Option<Plan>,
+}
+
+impl Default for Sampler {
+    fn default() -> Sampler {
+        Sampler::new()
+    }
+}
+
+impl Sampler {
+    /// Create a sampler without any clause.
+    pub fn new() -> Sampler {
+        Sampler {
+            counter: ApproxCounter::new(0.8, 0.2),
+            rng: Rng::new(0),
+            plan: None,
+        }
+    }
+
+    /// Seed the random hashes and the choice of samples.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.counter.set_seed(seed);
+        self.rng = Rng::new(seed);
+    }
+
+    /// Encode the hashes into clauses rather than adding them as XOR constraints.
+    pub fn set_native_xor(&mut self, native: bool) {
+        self.counter.set_native_xor(native);
+    }
+
+    /// Give up and fail with `Status::Unknown` once the terminator stops, its conflict limit
+    /// applying to each call of the solvers.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.counter.set_terminator(terminator);
+    }
+
+    /// Add a clause.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        self.plan = None;
+        self.counter.add_clause(clause);
+    }
+
+    /// Add an XOR constraint, given as literals whose exclusive or is true.
+    pub fn add_xor(&mut self, xor: &[i32]) {
+        self.plan = None;
+        self.counter.add_xor(xor);
+    }
+
+    /// Sample the assignments to the given variables only, such as those of a `c ind` line.
+    pub fn set_projection(&mut self, vars: &[i32]) {
+        self.plan = None;
+        self.counter.set_projection(vars);
+    }
+
+    /// Add the clauses and XOR constraints encoded so far, and sample the values of the given
+    /// words only. The values of a sample are given by `Encoder::decode`.
+    pub fn project_words(&mut self, encoder: &mut Encoder, words: &[&Word]) {
+        self.plan = None;
+        self.counter.project_words(encoder, words);
+    }
+
+    /// A random model, with one literal per variable as returned by `Solver::model`. Only the
+    /// sampling variables are distributed near uniformly. Fails with `Status::Unsat` if the
+    /// clauses are unsatisfiable, or with `Status::Unknown` if stopped by the terminator or if no
+    /// hashing gave a cell of the right size, which the approximate count makes unlikely.
+    pub fn sample(&mut self) -> Result<Vec<i32>, Status> {
+        let pivot = (4.03 * (1.0 + 1.0 / KAPPA).powi(2)).ceil();
+        let hi = (1.0 + (1.0 + KAPPA) * pivot) as usize;
+        let lo = (pivot / (1.0 + KAPPA)) as usize;
+
+        if self.plan.is_none() {
+            self.plan = Some(self.plan(hi, pivot)?);
+        }
+
+        let q = match self.plan.as_ref().unwrap() {
+            Plan::Unsat => return Err(Status::Unsat),
+
+            Plan::Exact(models) => {
+                let i = self.rng.below(models.len() as u64) as usize;
+
+                return Ok(models[i].clone());
+            }
+
+            Plan::Hashed(q) => *q,
+        };
+
+        let mut cells = self.counter.cells();
+
+        // a hashing may fail to give a cell of the right size, in which case another is drawn
+        for _ in 0..RETRIES {
+            for m in q.saturating_sub(3)..=q.min(cells.len()) {
+                let mut models = cells.enumerate_hashed(m, hi + 1, &mut self.rng);
+
+                if cells.stopped() {
+                    return Err(Status::Unknown);
+                }
+
+                if (lo..=hi).contains(&models.len()) {
+                    let i = self.rng.below(models.len() as u64) as usize;
+                    let mut model = models.swap_remove(i);
+
+                    model.truncate(self.counter.num_vars());
+
+                    return Ok(model);
+                }
+            }
+
+            cells.rehash();
+        }
+
+        Err(Status::Unknown)
+    }
+
+    fn plan(&mut self, hi: usize, pivot: f64) -> Result<Plan, Status> {
+        let mut cells = self.counter.cells();
+        let mut models = cells.enumerate(0, hi + 1);
+
+        if cells.stopped() {
+            return Err(Status::Unknown);
+        }
+
+        if models.is_empty() {
+            return Ok(Plan::Unsat);
+        }
+
+        if models.len() <= hi {
+            for model in models.iter_mut() {
+                model.truncate(self.counter.num_vars());
+            }
+
+            return Ok(Plan::Exact(models));
+        }
+
+        // the number of hashes expected to leave about a pivot of models
+        let count = self.counter.count().to_f64();
+
+        if self.counter.status() == Status::Unknown {
+            return Err(Status::Unknown);
+        }
+
+        Ok(Plan::Hashed(
+            (count.log2() + 1.8f64.log2() - pivot.log2()).ceil() as usize,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::words::Bits;
+
+    #[test]
+    fn words_01() {
+        let bits = Bits::new();
+        let a = Word::var(&bits, 6);
+        let b = Word::var(&bits, 6);
+        let bound = Word::from_u64(&bits, 6, 40);
+        let less = (&a * &b).less_than(&bound);
+
+        let mut encoder = Encoder::new(&bits, 0);
+        let mut sampler = Sampler::new();
+
+        sampler.add_clause(&[encoder.lit(less.ids()[0])]);
+        sampler.project_words(&mut encoder, &[&a, &b]);
+        sampler.set_seed(4);
+
+        let mut seen = [0; 4];
+
+        for _ in 0..100 {
+            let model = sampler.sample().unwrap();
+            let (x, y) = (encoder.decode(&a, &model), encoder.decode(&b, &model));
+
+            assert!(x * y % 64 < 40);
+
+            seen[(x % 2 + 2 * (y % 2)) as usize] += 1;
+        }
+
+        // about a quarter of the pairs fall in each class of parities
+        assert!(seen.iter().all(|n| (12..=40).contains(n)));
+    }
+
+    #[test]
+    fn simple_01() {
+        let mut sampler = Sampler::new();
+
+        // exactly one of 1, 2 and 3
+        for clause in &[vec![1, 2, 3], vec![-1, -2], vec![-1, -3], vec![-2, -3]] {
+            sampler.add_clause(clause);
+        }
+
+        let mut seen = [0; 3];
+
+        for _ in 0..300 {
+            let model = sampler.sample().unwrap();
+            let i = model.iter().position(|l| *l > 0).unwrap();
+
+            seen[i] += 1;
+        }
+
+        assert!(seen.iter().all(|n| (70..=130).contains(n)));
+
+        sampler.add_clause(&[-1]);
+        sampler.add_clause(&[-2]);
+        sampler.add_clause(&[-3]);
+
+        assert_eq!(sampler.sample(), Err(Status::Unsat));
+    }
+}
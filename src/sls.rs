@@ -0,0 +1,355 @@
+use crate::rng::Rng;
+use crate::solver::{index, Solver};
+use crate::Status;
+
+/// How local search picks the variable to flip in a falsified clause.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Heuristic {
+    /// probSAT: each variable with probability proportional to `(1 + break)^-cb`, where the
+    /// noise parameter is the exponent `cb`.
+    ProbSat,
+
+    /// WalkSAT/SKC: a variable breaking no clause if there is one, or else with the noise
+    /// parameter as probability a random variable, and otherwise one breaking the fewest.
+    WalkSat,
+}
+
+/// Stochastic local search over a clause set. Starting from a random assignment, variables of
+/// randomly chosen falsified clauses are flipped until every clause is satisfied or the flip
+/// limit is reached, in which case the search restarts from a new random assignment, up to a
+/// number of tries. The break count of a variable is the number of clauses it alone satisfies,
+/// which flipping it would falsify.
+///
+/// Local search cannot prove unsatisfiability, but the best assignment it finds makes good
+/// initial phases for the solver.
+pub struct LocalSearch {
+    num_vars: usize,
+    clauses: Vec<Vec<i32>>,
+    occurs: Vec<Vec<usize>>,
+
+    heuristic: Heuristic,
+    noise: f64,
+    max_flips: u64,
+    max_tries: u64,
+    rng: Rng,
+
+    values: Vec<bool>,
+    satisfied: Vec<u32>,
+    falsified: Vec<usize>,
+    position: Vec<usize>,
+
+    best: Vec<bool>,
+    best_falsified: usize,
+    flips: u64,
+}
+
+impl LocalSearch {
+    /// Create a search over the given clauses, with probSAT tuned for 3-SAT. Literals within
+    /// each clause are deduplicated, and tautologies are dropped.
+    pub fn new(clauses: &[Vec<i32>]) -> LocalSearch {
+        let num_vars = clauses
+            .iter()
+            .flatten()
+            .map(|l| l.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+
+        let mut kept = Vec::new();
+        let mut occurs = vec![Vec::new(); 2 * num_vars];
+
+        for clause in clauses {
+            let mut clause = clause.clone();
+
+            clause.sort_unstable_by_key(|l| (l.abs(), *l));
+            clause.dedup();
+
+            if clause.windows(2).any(|w| w[0] == -w[1]) {
+                continue;
+            }
+
+            for l in &clause {
+                occurs[index(*l)].push(kept.len());
+            }
+
+            kept.push(clause);
+        }
+
+        LocalSearch {
+            num_vars,
+            clauses: kept,
+            occurs,
+
+            heuristic: Heuristic::ProbSat,
+            noise: 2.3,
+            max_flips: 100_000,
+            max_tries: 10,
+            rng: Rng::new(0),
+
+            values: vec![false; num_vars + 1],
+            satisfied: Vec::new(),
+            falsified: Vec::new(),
+            position: Vec::new(),
+
+            best: vec![false; num_vars + 1],
+            best_falsified: usize::MAX,
+            flips: 0,
+        }
+    }
+
+    /// Use the given heuristic and noise parameter.
+    pub fn set_heuristic(&mut self, heuristic: Heuristic, noise: f64) {
+        self.heuristic = heuristic;
+        self.noise = noise;
+    }
+
+    /// Restart after `max_flips` flips without satisfying every clause, and give up after
+    /// `max_tries` tries.
+    pub fn set_limits(&mut self, max_flips: u64, max_tries: u64) {
+        self.max_flips = max_flips;
+        self.max_tries = max_tries;
+    }
+
+    /// Seed the random assignments and choices.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// The number of variables.
+    pub fn num_vars(&self) -> usize {
+        self.num_vars
+    }
+
+    /// The total number of flips made.
+    pub fn flips(&self) -> u64 {
+        self.flips
+    }
+
+    /// Search for a satisfying assignment, returning `Status::Sat` if one is found and
+    /// `Status::Unknown` otherwise.
+    pub fn solve(&mut self) -> Status {
+        if self.clauses.iter().any(|c| c.is_empty()) {
+            return Status::Unknown;
+        }
+
+        for _ in 0..self.max_tries {
+            self.restart();
+
+            for _ in 0..self.max_flips {
+                if self.falsified.is_empty() {
+                    break;
+                }
+
+                let c = self.falsified[self.rng.below(self.falsified.len() as u64) as usize];
+                let v = self.pick(c);
+
+                self.flip(v);
+                self.flips += 1;
+
+                if self.falsified.len() < self.best_falsified {
+                    self.best.copy_from_slice(&self.values);
+                    self.best_falsified = self.falsified.len();
+                }
+            }
+
+            if self.falsified.is_empty() {
+                return Status::Sat;
+            }
+        }
+
+        Status::Unknown
+    }
+
+    /// The assignment falsifying the fewest clauses found so far, as one literal per variable.
+    /// After a successful search, this is a model.
+    pub fn model(&self) -> Vec<i32> {
+        (1..=self.num_vars)
+            .map(|v| if self.best[v] { v as i32 } else { -(v as i32) })
+            .collect()
+    }
+
+    /// Make the solver branch on the values of the best assignment found so far.
+    pub fn set_phases(&self, solver: &mut Solver) {
+        for l in self.model() {
+            solver.set_phase(l);
+        }
+    }
+
+    fn restart(&mut self) {
+        for v in 1..=self.num_vars {
+            self.values[v] = self.rng.next_bool();
+        }
+
+        self.satisfied = vec![0; self.clauses.len()];
+        self.falsified.clear();
+        self.position = vec![usize::MAX; self.clauses.len()];
+
+        for c in 0..self.clauses.len() {
+            let values = &self.values;
+
+            self.satisfied[c] = self.clauses[c]
+                .iter()
+                .filter(|l| values[l.unsigned_abs() as usize] == (**l > 0))
+                .count() as u32;
+
+            if self.satisfied[c] == 0 {
+                self.position[c] = self.falsified.len();
+                self.falsified.push(c);
+            }
+        }
+
+        if self.falsified.len() < self.best_falsified {
+            self.best.copy_from_slice(&self.values);
+            self.best_falsified = self.falsified.len();
+        }
+    }
+
+    /// The number of clauses only satisfied by the variable.
+    fn break_count(&self, v: usize) -> usize {
+        let l = if self.values[v] {
+            v as i32
+        } else {
+            -(v as i32)
+        };
+
+        self.occurs[index(l)]
+            .iter()
+            .filter(|c| self.satisfied[**c] == 1)
+            .count()
+    }
+
+    fn pick(&mut self, c: usize) -> usize {
+        let vars: Vec<usize> = self.clauses[c]
+            .iter()
+            .map(|l| l.unsigned_abs() as usize)
+            .collect();
+
+        let breaks: Vec<usize> = vars.iter().map(|v| self.break_count(*v)).collect();
+
+        match self.heuristic {
+            Heuristic::ProbSat => {
+                let weights: Vec<f64> = breaks
+                    .iter()
+                    .map(|b| (1.0 + *b as f64).powf(-self.noise))
+                    .collect();
+
+                let mut x = self.rng.next_f64() * weights.iter().sum::<f64>();
+
+                for (v, w) in vars.iter().zip(weights.iter()) {
+                    if x < *w {
+                        return *v;
+                    }
+
+                    x -= w;
+                }
+
+                vars[vars.len() - 1]
+            }
+
+            Heuristic::WalkSat => {
+                let min = *breaks.iter().min().unwrap();
+
+                if min > 0 && self.rng.next_f64() < self.noise {
+                    return vars[self.rng.below(vars.len() as u64) as usize];
+                }
+
+                let best: Vec<usize> = (0..vars.len()).filter(|i| breaks[*i] == min).collect();
+
+                vars[best[self.rng.below(best.len() as u64) as usize]]
+            }
+        }
+    }
+
+    fn flip(&mut self, v: usize) {
+        let was = if self.values[v] {
+            v as i32
+        } else {
+            -(v as i32)
+        };
+
+        self.values[v] = !self.values[v];
+
+        for i in 0..self.occurs[index(was)].len() {
+            let c = self.occurs[index(was)][i];
+
+            self.satisfied[c] -= 1;
+
+            if self.satisfied[c] == 0 {
+                self.position[c] = self.falsified.len();
+                self.falsified.push(c);
+            }
+        }
+
+        for i in 0..self.occurs[index(-was)].len() {
+            let c = self.occurs[index(-was)][i];
+
+            self.satisfied[c] += 1;
+
+            if self.satisfied[c] == 1 {
+                // swap the clause out of the falsified list
+                let p = self.position[c];
+                let last = self.falsified.pop().unwrap();
+
+                if last != c {
+                    self.falsified[p] = last;
+                    self.position[last] = p;
+                }
+
+                self.position[c] = usize::MAX;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::random_3sat;
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(9);
+
+        for heuristic in [Heuristic::ProbSat, Heuristic::WalkSat] {
+            for _ in 0..5 {
+                // below the satisfiability threshold of 4.27 clauses per variable
+                let clauses = random_3sat(&mut rng, 200, 700);
+                let mut search = LocalSearch::new(&clauses);
+
+                match heuristic {
+                    Heuristic::ProbSat => search.set_heuristic(heuristic, 2.3),
+                    Heuristic::WalkSat => search.set_heuristic(heuristic, 0.567),
+                }
+
+                search.set_seed(rng.next_u64());
+
+                assert_eq!(search.solve(), Status::Sat);
+
+                let model = search.model();
+
+                assert!(clauses.iter().all(|c| c.iter().any(|l| model.contains(l))));
+            }
+        }
+    }
+
+    #[test]
+    fn phases_01() {
+        let mut rng = Rng::new(10);
+        let clauses = random_3sat(&mut rng, 100, 500);
+        let mut search = LocalSearch::new(&clauses);
+
+        search.set_limits(1000, 1);
+        search.solve();
+
+        let mut solver = Solver::new();
+
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+
+        search.set_phases(&mut solver);
+
+        // unsatisfiable, so the search falls short, but the phases do not change the answer
+        assert_eq!(search.solve(), Status::Unknown);
+        assert_eq!(solver.solve(), Status::Unsat);
+    }
+}
@@ -0,0 +1,313 @@
+use std::io::{self, BufRead, Write};
+
+use crate::Status;
+
+/// Write a result in the SAT competition output format: an `s` status line followed, for
+/// satisfiable instances, by the model on `v` lines terminated by `0`.
+pub fn write_solution<W>(io: &mut W, status: Status, model: &[i32]) -> io::Result<()>
+where
+    W: Write,
+{
+    match status {
+        Status::Sat => writeln!(io, "s SATISFIABLE")?,
+        Status::Unsat => writeln!(io, "s UNSATISFIABLE")?,
+        Status::Unknown => writeln!(io, "s UNKNOWN")?,
+    }
+
+    if status != Status::Sat {
+        return Ok(());
+    }
+
+    for chunk in model.chunks(10) {
+        write!(io, "v")?;
+
+        for literal in chunk {
+            write!(io, " {}", literal)?;
+        }
+
+        writeln!(io)?;
+    }
+
+    writeln!(io, "v 0")
+}
+
+/// Write a MaxSAT result in the MaxSAT evaluation output format: an `s` status line followed
+/// by the best model found as a single `v` line of zeros and ones. `Status::Sat` means that the
+/// model is optimal. Costs are reported separately, on `o` lines, as they are found.
+pub fn write_maxsat_solution<W>(io: &mut W, status: Status, model: &[i32]) -> io::Result<()>
+where
+    W: Write,
+{
+    match status {
+        Status::Sat => writeln!(io, "s OPTIMUM FOUND")?,
+        Status::Unsat => writeln!(io, "s UNSATISFIABLE")?,
+        Status::Unknown if !model.is_empty() => writeln!(io, "s SATISFIABLE")?,
+        Status::Unknown => writeln!(io, "s UNKNOWN")?,
+    }
+
+    if status == Status::Unsat || model.is_empty() {
+        return Ok(());
+    }
+
+    let values: String = model
+        .iter()
+        .map(|l| if *l > 0 { '1' } else { '0' })
+        .collect();
+
+    writeln!(io, "v {}", values)
+}
+
+/// Write a pseudo-Boolean result in the PB competition output format: an `s` status line
+/// followed by the model as `v` lines of variables `xN` and their complements `-xN`. For
+/// optimization problems `optimal` distinguishes `Status::Sat` as an optimum.
+pub fn write_pb_solution<W>(
+    io: &mut W,
+    status: Status,
+    optimal: bool,
+    model: &[i32],
+) -> io::Result<()>
+where
+    W: Write,
+{
+    match status {
+        Status::Sat if optimal => writeln!(io, "s OPTIMUM FOUND")?,
+        Status::Sat => writeln!(io, "s SATISFIABLE")?,
+        Status::Unsat => writeln!(io, "s UNSATISFIABLE")?,
+        Status::Unknown if !model.is_empty() => writeln!(io, "s SATISFIABLE")?,
+        Status::Unknown => writeln!(io, "s UNKNOWN")?,
+    }
+
+    if status == Status::Unsat {
+        return Ok(());
+    }
+
+    for chunk in model.chunks(10) {
+        write!(io, "v")?;
+
+        for literal in chunk {
+            let sign = if *literal < 0 { "-" } else { "" };
+
+            write!(io, " {}x{}", sign, literal.abs())?;
+        }
+
+        writeln!(io)?;
+    }
+
+    Ok(())
+}
+
+/// Read a result in the SAT competition output format. Comment lines are skipped. A model
+/// assigning both polarities of a variable is rejected.
+pub fn read_solution<R>(io: R) -> io::Result<(Status, Vec<i32>)>
+where
+    R: BufRead,
+{
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+
+    let mut status = None;
+    let mut model = Vec::new();
+
+    for line in io.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if let Some(rest) = line.strip_prefix('s') {
+            status = Some(match rest.trim() {
+                "SATISFIABLE" => Status::Sat,
+                "UNSATISFIABLE" => Status::Unsat,
+                "UNKNOWN" => Status::Unknown,
+                other => return Err(invalid(format!("unknown status '{}'", other))),
+            });
+        } else if let Some(rest) = line.strip_prefix('v') {
+            for token in rest.split_whitespace() {
+                let literal: i32 = token
+                    .parse()
+                    .map_err(|_| invalid(format!("invalid literal '{}'", token)))?;
+
+                if literal != 0 {
+                    model.push(literal);
+                }
+            }
+        }
+    }
+
+    let status = status.ok_or_else(|| invalid("missing status line".to_string()))?;
+
+    model.sort_unstable_by_key(|l| (l.unsigned_abs(), *l));
+    model.dedup();
+
+    if model.windows(2).any(|w| w[0] == -w[1]) {
+        return Err(invalid(
+            "model assigns both polarities of a variable".to_string(),
+        ));
+    }
+
+    Ok((status, model))
+}
+
+/// Check a model against a stream of clauses, such as a `Dimacs` iterator. Returns the index and
+/// contents of the first clause with no literal in the model.
+pub fn verify<I>(clauses: I, model: &[i32]) -> Option<(usize, Vec<i32>)>
+where
+    I: IntoIterator<Item = Vec<i32>>,
+{
+    let values = values(model);
+    let is_true = |l: &i32| values.get(l.unsigned_abs() as usize) == Some(&Some(*l > 0));
+
+    clauses
+        .into_iter()
+        .enumerate()
+        .find(|(_, clause)| !clause.iter().any(is_true))
+}
+
+/// Check a model against XOR constraints, such as those of `Dimacs::xors`. Returns the index and
+/// contents of the first constraint with an even number of true literals, or with a variable
+/// missing from the model.
+pub fn verify_xors(xors: &[Vec<i32>], model: &[i32]) -> Option<(usize, Vec<i32>)> {
+    let values = values(model);
+
+    let satisfied = |xor: &Vec<i32>| {
+        xor.iter()
+            .try_fold(false, |parity, l| {
+                let value = values.get(l.unsigned_abs() as usize).copied().flatten()?;
+
+                Some(parity ^ (value == (*l > 0)))
+            })
+            .unwrap_or(false)
+    };
+
+    xors.iter()
+        .enumerate()
+        .find(|(_, xor)| !satisfied(xor))
+        .map(|(index, xor)| (index, xor.clone()))
+}
+
+/// The value of each variable in the model, indexed by variable.
+fn values(model: &[i32]) -> Vec<Option<bool>> {
+    let size = model
+        .iter()
+        .map(|l| l.unsigned_abs() as usize)
+        .max()
+        .unwrap_or(0);
+
+    let mut values = vec![None; size + 1];
+
+    for literal in model {
+        values[literal.unsigned_abs() as usize] = Some(*literal > 0);
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dimacs::Dimacs;
+    use crate::dp::DavisPutnam;
+
+    #[test]
+    fn roundtrip_01() {
+        let model: Vec<i32> = (1..=23).map(|v| if v % 3 == 0 { -v } else { v }).collect();
+        let mut out = Vec::new();
+
+        write_solution(&mut out, Status::Sat, &model).unwrap();
+
+        let (status, parsed) = read_solution(&out[..]).unwrap();
+
+        assert_eq!(status, Status::Sat);
+        assert_eq!(parsed, model);
+    }
+
+    #[test]
+    fn maxsat_01() {
+        let mut out = Vec::new();
+
+        write_maxsat_solution(&mut out, Status::Sat, &[1, -2, -3, 4]).unwrap();
+        write_maxsat_solution(&mut out, Status::Unknown, &[-1]).unwrap();
+        write_maxsat_solution(&mut out, Status::Unknown, &[]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "s OPTIMUM FOUND\nv 1001\ns SATISFIABLE\nv 0\ns UNKNOWN\n"
+        );
+    }
+
+    #[test]
+    fn pb_01() {
+        let mut out = Vec::new();
+
+        write_pb_solution(&mut out, Status::Sat, true, &[1, -2]).unwrap();
+        write_pb_solution(&mut out, Status::Unsat, false, &[]).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "s OPTIMUM FOUND\nv x1 -x2\ns UNSATISFIABLE\n"
+        );
+    }
+
+    #[test]
+    fn read_01() {
+        let text = "c comment\ns UNSATISFIABLE\n";
+
+        assert_eq!(
+            read_solution(text.as_bytes()).unwrap(),
+            (Status::Unsat, vec![])
+        );
+
+        assert!(read_solution("v 1 -1 0\ns SATISFIABLE\n".as_bytes()).is_err());
+        assert!(read_solution("v 1 2 0\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn verify_01() {
+        let cnf = "p cnf 3 3\n1 2 0\n-1 3 0\n-2 -3 0\n";
+
+        assert_eq!(verify(Dimacs::new(cnf.as_bytes()), &[1, -2, 3]), None);
+        assert_eq!(
+            verify(Dimacs::new(cnf.as_bytes()), &[1, 2, 3]),
+            Some((2, vec![-2, -3]))
+        );
+        assert_eq!(
+            verify(Dimacs::new(cnf.as_bytes()), &[1, -2]),
+            Some((1, vec![-1, 3]))
+        );
+    }
+
+    #[test]
+    fn verify_xors_01() {
+        let cnf = "p cnf 5 1\n1 2 3 0\nx4 5 0\nx-1 2 0\n";
+        let mut dimacs = Dimacs::new(cnf.as_bytes());
+
+        assert_eq!(verify(dimacs.by_ref(), &[1, 2, 3, -4, -5]), None);
+        assert_eq!(
+            verify_xors(dimacs.xors(), &[1, 2, 3, -4, -5]),
+            Some((0, vec![4, 5]))
+        );
+        assert_eq!(
+            verify_xors(dimacs.xors(), &[1, -2, 3, -4, 5]),
+            Some((1, vec![-1, 2]))
+        );
+        assert_eq!(verify_xors(dimacs.xors(), &[1, 2, 3, -4, 5]), None);
+        assert_eq!(
+            verify_xors(dimacs.xors(), &[1, 2, 3, 4]),
+            Some((0, vec![4, 5]))
+        );
+    }
+
+    #[test]
+    fn verify_02() {
+        let clauses = vec![
+            vec![1, -5, 4],
+            vec![-1, 5, 3, 4],
+            vec![-3, -4],
+            vec![2, 3],
+            vec![-2, -3, 5],
+            vec![-4, -5],
+        ];
+
+        let mut dp = DavisPutnam::new(clauses.clone());
+
+        assert_eq!(dp.solve(), Status::Sat);
+        assert_eq!(verify(clauses, &dp.model()), None);
+    }
+}
@@ -0,0 +1,1643 @@
+use std::time::Instant;
+
+use crate::drat::Drat;
+use crate::rng::Rng;
+use crate::stats::{Observer, Stats};
+use crate::terminator::Terminator;
+use crate::xor::{Deduction, Gauss};
+use crate::Status;
+
+/// The number of conflicts between imports of clauses learnt elsewhere, whatever the restarts.
+const IMPORT_INTERVAL: u64 = 2000;
+
+/// Internal literal encoding: `2 * var + sign`, with variables numbered from zero.
+type Lit = u32;
+
+fn lit(literal: i32) -> Lit {
+    assert_ne!(literal, 0);
+    ((literal.unsigned_abs() - 1) << 1) | (literal < 0) as u32
+}
+
+fn dimacs(l: Lit) -> i32 {
+    let v = (l >> 1) as i32 + 1;
+
+    if l & 1 == 1 {
+        -v
+    } else {
+        v
+    }
+}
+
+fn var(l: Lit) -> usize {
+    (l >> 1) as usize
+}
+
+/// The position of a DIMACS literal in tables indexed by literal, such as occurrence lists of
+/// `2 * num_vars` entries.
+pub(crate) fn index(literal: i32) -> usize {
+    lit(literal) as usize
+}
+
+fn neg(l: Lit) -> Lit {
+    l ^ 1
+}
+
+/// Value of a literal under the per-variable assignment: 1 true, -1 false, 0 unassigned.
+fn value(assigns: &[i8], l: Lit) -> i8 {
+    let v = assigns[var(l)];
+
+    if l & 1 == 1 {
+        -v
+    } else {
+        v
+    }
+}
+
+/// Luby restart sequence scaled by `y`.
+fn luby(y: f64, mut x: u64) -> f64 {
+    let mut size = 1;
+    let mut seq = 0;
+
+    while size < x + 1 {
+        seq += 1;
+        size = 2 * size + 1;
+    }
+
+    while size - 1 != x {
+        size = (size - 1) >> 1;
+        seq -= 1;
+        x %= size;
+    }
+
+    y.powi(seq)
+}
+
+struct Clause {
+    lits: Vec<Lit>,
+    learnt: bool,
+    lbd: u32,
+    activity: f64,
+    deleted: bool,
+}
+
+/// Why a variable was assigned: a clause, or a clause derived from the XOR constraints and kept
+/// in `explanations` until the assignment is undone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Reason {
+    Clause(usize),
+    Xor(usize),
+}
+
+#[derive(Clone, Copy)]
+struct Watch {
+    clause: usize,
+    blocker: Lit,
+}
+
+/// Binary max-heap of variables ordered by activity.
+#[derive(Default)]
+struct Heap {
+    heap: Vec<usize>,
+    indices: Vec<Option<usize>>,
+}
+
+impl Heap {
+    fn contains(&self, v: usize) -> bool {
+        self.indices[v].is_some()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    fn grow(&mut self, n: usize) {
+        if self.indices.len() < n {
+            self.indices.resize(n, None);
+        }
+    }
+
+    fn insert(&mut self, v: usize, activity: &[f64]) {
+        if self.contains(v) {
+            return;
+        }
+
+        self.indices[v] = Some(self.heap.len());
+        self.heap.push(v);
+        self.up(self.heap.len() - 1, activity);
+    }
+
+    fn update(&mut self, v: usize, activity: &[f64]) {
+        if let Some(i) = self.indices[v] {
+            self.up(i, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<usize> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+
+        self.indices[top] = None;
+
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.indices[last] = Some(0);
+            self.down(0, activity);
+        }
+
+        Some(top)
+    }
+
+    fn rebuild(&mut self, activity: &[f64]) {
+        for i in (0..self.heap.len() / 2).rev() {
+            self.down(i, activity);
+        }
+    }
+
+    fn up(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+
+        while i > 0 {
+            let parent = (i - 1) / 2;
+
+            if activity[self.heap[parent]] >= activity[v] {
+                break;
+            }
+
+            self.heap[i] = self.heap[parent];
+            self.indices[self.heap[i]] = Some(i);
+            i = parent;
+        }
+
+        self.heap[i] = v;
+        self.indices[v] = Some(i);
+    }
+
+    fn down(&mut self, mut i: usize, activity: &[f64]) {
+        let v = self.heap[i];
+
+        loop {
+            let left = 2 * i + 1;
+
+            if left >= self.heap.len() {
+                break;
+            }
+
+            let right = left + 1;
+            let child = if right < self.heap.len()
+                && activity[self.heap[right]] > activity[self.heap[left]]
+            {
+                right
+            } else {
+                left
+            };
+
+            if activity[self.heap[child]] <= activity[v] {
+                break;
+            }
+
+            self.heap[i] = self.heap[child];
+            self.indices[self.heap[i]] = Some(i);
+            i = child;
+        }
+
+        self.heap[i] = v;
+        self.indices[v] = Some(i);
+    }
+}
+
+/// Callback polled while solving, returning true to give up.
+pub type Terminate = Box<dyn FnMut() -> bool + Send>;
+
+/// Callback receiving learnt clauses.
+pub type Learn = Box<dyn FnMut(&[i32]) + Send>;
+
+/// Callback polled at every restart for clauses to add as learnt clauses.
+pub type Import = Box<dyn FnMut() -> Vec<Vec<i32>> + Send>;
+
+/// How many conflicts the solver allows between restarts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Restarts {
+    /// The Luby sequence 1, 1, 2, 1, 1, 2, 4, ... scaled by the given number of conflicts.
+    Luby(u64),
+
+    /// A first number of conflicts, multiplied by the given factor after each restart.
+    Geometric(u64, f64),
+}
+
+/// Conflict-driven clause learning SAT solver. Literals are given in DIMACS convention: variable
+/// `v` is the literal `v` and its complement is `-v`.
+pub struct Solver {
+    ok: bool,
+
+    clauses: Vec<Clause>,
+    watches: Vec<Vec<Watch>>,
+    learnts: usize,
+    max_learnts: f64,
+
+    assigns: Vec<i8>,
+    level: Vec<u32>,
+    reason: Vec<Option<Reason>>,
+    polarity: Vec<bool>,
+    seen: Vec<bool>,
+
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+
+    activity: Vec<f64>,
+    var_inc: f64,
+    clause_inc: f64,
+    heap: Heap,
+
+    xors: Gauss,
+    xhead: usize,
+    explanations: Vec<(usize, Vec<Lit>)>,
+
+    scopes: Vec<Lit>,
+    assumptions: Vec<Lit>,
+    failed: Vec<i32>,
+
+    model: Vec<bool>,
+    rng: Option<Rng>,
+    deadline: Option<Instant>,
+    terminate: Option<Terminate>,
+    terminator: Option<Terminator>,
+    learn: Option<(usize, Learn)>,
+    import: Option<Import>,
+    next_import: u64,
+    restarts: Restarts,
+    proof: Option<Drat>,
+    observer: Option<Box<dyn Observer>>,
+
+    stats: Stats,
+    started: u64,
+}
+
+impl Default for Solver {
+    fn default() -> Solver {
+        Solver::new()
+    }
+}
+
+impl Solver {
+    /// Create a new, empty solver.
+    pub fn new() -> Solver {
+        Solver {
+            ok: true,
+
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            learnts: 0,
+            max_learnts: 0.0,
+
+            assigns: Vec::new(),
+            level: Vec::new(),
+            reason: Vec::new(),
+            polarity: Vec::new(),
+            seen: Vec::new(),
+
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+
+            activity: Vec::new(),
+            var_inc: 1.0,
+            clause_inc: 1.0,
+            heap: Heap::default(),
+
+            xors: Gauss::new(),
+            xhead: 0,
+            explanations: Vec::new(),
+
+            scopes: Vec::new(),
+            assumptions: Vec::new(),
+            failed: Vec::new(),
+
+            model: Vec::new(),
+            rng: None,
+            deadline: None,
+            terminate: None,
+            terminator: None,
+            learn: None,
+            import: None,
+            next_import: 0,
+            restarts: Restarts::Luby(100),
+            proof: None,
+            observer: None,
+
+            stats: Stats::default(),
+            started: 0,
+        }
+    }
+
+    /// The number of variables known to the solver.
+    pub fn num_vars(&self) -> usize {
+        self.assigns.len()
+    }
+
+    /// Seed the solver. Variable activities are perturbed by small random amounts so that
+    /// differently seeded solvers explore the search space in different orders.
+    pub fn set_seed(&mut self, seed: u64) {
+        let mut rng = Rng::new(seed);
+
+        for activity in self.activity.iter_mut() {
+            *activity += rng.next_f64() * 1e-5;
+        }
+
+        self.heap.rebuild(&self.activity);
+        self.rng = Some(rng);
+    }
+
+    /// Branch on the given literal when deciding its variable, until the solver assigns the
+    /// variable otherwise, such as to start from an assignment found by local search.
+    pub fn set_phase(&mut self, literal: i32) {
+        let l = lit(literal);
+
+        self.reserve(var(l) + 1);
+        self.polarity[var(l)] = l & 1 == 1;
+    }
+
+    /// Give up and report `Status::Unknown` once the deadline has passed.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Give up and report `Status::Unknown` as soon as the callback, polled at every conflict,
+    /// returns true.
+    pub fn set_terminate(&mut self, terminate: Option<Terminate>) {
+        self.terminate = terminate;
+    }
+
+    /// Give up and report `Status::Unknown` once the terminator stops, its conflict limit
+    /// counting the conflicts of each call to `solve_with_assumptions`.
+    pub fn set_terminator(&mut self, terminator: Terminator) {
+        self.terminator = Some(terminator);
+    }
+
+    /// Pass each learnt clause of at most `max_length` literals to the callback.
+    pub fn set_learn(&mut self, max_length: usize, learn: Option<Learn>) {
+        self.learn = learn.map(|f| (max_length, f));
+    }
+
+    /// Add the clauses returned by the callback at every restart, and at least every few
+    /// thousand conflicts, as learnt clauses that may later be deleted. They must be implied by
+    /// the clauses of the solver, such as clauses learnt by another solver on the same clauses,
+    /// and are not logged to the proof.
+    pub fn set_import(&mut self, import: Option<Import>) {
+        self.import = import;
+    }
+
+    /// Use the given restart policy, Luby restarts every 100 conflicts by default.
+    pub fn set_restarts(&mut self, restarts: Restarts) {
+        self.restarts = restarts;
+    }
+
+    /// Pass the events of the search to the observer.
+    pub fn set_observer(&mut self, observer: Option<Box<dyn Observer>>) {
+        self.observer = observer;
+    }
+
+    /// Log learnt and deleted clauses to a DRAT proof from now on.
+    pub fn set_proof(&mut self, proof: Drat) {
+        self.proof = Some(proof);
+    }
+
+    /// Stop logging and return the proof.
+    pub fn take_proof(&mut self) -> Option<Drat> {
+        self.proof.take()
+    }
+
+    /// Add a clause to the solver, in the innermost scope if any.
+    pub fn add_clause(&mut self, clause: &[i32]) {
+        match self.scopes.last() {
+            Some(a) => {
+                let mut clause = clause.to_vec();
+
+                clause.push(-dimacs(*a));
+                self.add_definition(&clause);
+            }
+
+            None => self.add_definition(clause),
+        }
+    }
+
+    /// Add a clause outside of any scope, so that it is kept when scopes are popped. Meant for
+    /// clauses that only define auxiliary variables, such as those of an `Encoder`.
+    pub fn add_definition(&mut self, clause: &[i32]) {
+        self.backtrack(0);
+
+        if !self.ok {
+            return;
+        }
+
+        let mut lits: Vec<Lit> = clause.iter().map(|l| lit(*l)).collect();
+
+        if let Some(max) = lits.iter().map(|l| var(*l)).max() {
+            self.reserve(max + 1);
+        }
+
+        lits.sort_unstable();
+        lits.dedup();
+
+        // complementary literals are adjacent once sorted
+        if lits.windows(2).any(|w| w[0] == neg(w[1])) {
+            return;
+        }
+
+        if lits.iter().any(|l| value(&self.assigns, *l) == 1) {
+            return;
+        }
+
+        let len = lits.len();
+
+        lits.retain(|l| value(&self.assigns, *l) == 0);
+
+        // literals falsified at the root are dropped, leaving a clause implied by unit propagation
+        if lits.len() < len {
+            self.log_add(&lits);
+        }
+
+        match lits.len() {
+            0 => self.ok = false,
+
+            1 => {
+                self.enqueue(lits[0], None);
+
+                if self.propagate().is_some() {
+                    self.ok = false;
+                    self.log_add(&[]);
+                }
+            }
+
+            _ => {
+                self.attach(lits, false, 0);
+            }
+        }
+    }
+
+    /// Add an XOR constraint, given as literals whose exclusive or must be true. XOR constraints
+    /// take part in propagation through incremental Gauss-Jordan elimination, whose deductions
+    /// are explained by clauses that are only kept while needed. Learnt clauses depending on
+    /// them are not justified by the proof. XOR constraints are kept when scopes are popped.
+    pub fn add_xor(&mut self, xor: &[i32]) {
+        self.backtrack(0);
+
+        if !self.ok {
+            return;
+        }
+
+        if let Some(max) = xor.iter().map(|l| var(lit(*l))).max() {
+            self.reserve(max + 1);
+        }
+
+        if !self.xors.add(xor) {
+            self.ok = false;
+            return;
+        }
+
+        let mut deductions = Vec::new();
+        let (assigns, level) = (&self.assigns, &self.level);
+
+        self.xors.rescan(
+            |v| match assigns[v as usize - 1] {
+                0 => None,
+                a => Some((a == 1, level[v as usize - 1])),
+            },
+            &mut deductions,
+        );
+
+        if self.deduce(deductions).is_some() || self.propagate().is_some() {
+            self.ok = false;
+        }
+    }
+
+    /// Open a scope for the clauses added until the matching `pop`. The scope is enabled by
+    /// assuming the activation variable `activation`, which must not occur in any clause.
+    pub fn push(&mut self, activation: i32) {
+        assert!(activation > 0);
+
+        self.reserve(activation as usize);
+        self.scopes.push(lit(activation));
+    }
+
+    /// Close the innermost scope, removing the clauses added in it for good, along with the
+    /// clauses learnt from them.
+    pub fn pop(&mut self) {
+        let a = self.scopes.pop().expect("no scope to pop");
+
+        self.add_definition(&[-dimacs(a)]);
+
+        if !self.ok {
+            return;
+        }
+
+        // the clauses of the scope are satisfied by the complement of its activation literal
+        let locked = self.locked();
+        let satisfied: Vec<usize> = (0..self.clauses.len())
+            .filter(|c| !locked[*c] && self.clauses[*c].lits.contains(&neg(a)))
+            .collect();
+
+        for c in satisfied {
+            let lits = std::mem::take(&mut self.clauses[c].lits);
+
+            self.log_delete(&lits);
+            self.clauses[c].deleted = true;
+
+            if self.clauses[c].learnt {
+                self.learnts -= 1;
+            }
+        }
+
+        self.compact();
+    }
+
+    /// Search for a satisfying assignment of the clauses added so far.
+    pub fn solve(&mut self) -> Status {
+        self.solve_with_assumptions(&[])
+    }
+
+    /// Search for a satisfying assignment in which all of the given literals are true. The
+    /// assumptions only hold for this call, and clauses may be added between calls.
+    pub fn solve_with_assumptions(&mut self, assumptions: &[i32]) -> Status {
+        self.model.clear();
+        self.failed.clear();
+
+        self.assumptions = self.scopes.clone();
+        self.assumptions.extend(assumptions.iter().map(|l| lit(*l)));
+
+        if let Some(max) = self.assumptions.iter().map(|l| var(*l)).max() {
+            self.reserve(max + 1);
+        }
+
+        if !self.ok {
+            return Status::Unsat;
+        }
+
+        self.started = self.stats.conflicts;
+
+        // a stop requested before the call is honoured without waiting for a conflict
+        if self.terminated() {
+            return Status::Unknown;
+        }
+
+        self.max_learnts = self
+            .max_learnts
+            .max(self.clauses.len() as f64 / 3.0 + 1000.0);
+
+        let start = Instant::now();
+        let status = self.run();
+
+        self.stats.search_time += start.elapsed();
+
+        status
+    }
+
+    /// Search with restarts until a conclusion is reached.
+    fn run(&mut self) -> Status {
+        let mut restarts = 0;
+
+        loop {
+            let budget = match self.restarts {
+                Restarts::Luby(unit) => (luby(2.0, restarts) * unit as f64) as u64,
+                Restarts::Geometric(first, factor) => {
+                    (first as f64 * factor.powi(restarts as i32)) as u64
+                }
+            };
+
+            if let Some(status) = self.search(budget) {
+                let scopes = &self.scopes;
+
+                self.failed.retain(|l| !scopes.contains(&lit(*l)));
+                self.backtrack(0);
+
+                return status;
+            }
+
+            restarts += 1;
+            self.stats.restarts += 1;
+
+            if let Some(observer) = &mut self.observer {
+                observer.restart();
+            }
+
+            if !self.import() {
+                return Status::Unsat;
+            }
+        }
+    }
+
+    /// The assumptions of the last call to `solve_with_assumptions` responsible for it being
+    /// unsatisfiable. Empty if the clauses of the open scopes are unsatisfiable without any
+    /// assumption.
+    pub fn failed(&self) -> &[i32] {
+        &self.failed
+    }
+
+    /// The value of a literal in the last model found, if the variable was known to the solver.
+    pub fn value(&self, literal: i32) -> Option<bool> {
+        let l = lit(literal);
+
+        self.model.get(var(l)).map(|v| *v != (l & 1 == 1))
+    }
+
+    /// The number of conflicts so far, over all calls.
+    pub fn conflicts(&self) -> u64 {
+        self.stats.conflicts
+    }
+
+    /// The statistics so far, over all calls.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// The last model found, as one literal per variable.
+    pub fn model(&self) -> Vec<i32> {
+        self.model
+            .iter()
+            .enumerate()
+            .map(|(v, b)| if *b { v as i32 + 1 } else { -(v as i32 + 1) })
+            .collect()
+    }
+
+    fn reserve(&mut self, n: usize) {
+        let old = self.num_vars();
+
+        if n <= old {
+            return;
+        }
+
+        self.assigns.resize(n, 0);
+        self.level.resize(n, 0);
+        self.reason.resize(n, None);
+        self.polarity.resize(n, false);
+        self.seen.resize(n, false);
+        self.watches.resize_with(2 * n, Vec::new);
+        self.heap.grow(n);
+
+        for v in old..n {
+            let activity = match &mut self.rng {
+                Some(rng) => rng.next_f64() * 1e-5,
+                None => 0.0,
+            };
+
+            self.activity.push(activity);
+            self.heap.insert(v, &self.activity);
+        }
+    }
+
+    fn log_add(&mut self, lits: &[Lit]) {
+        if let Some(proof) = &mut self.proof {
+            proof.add(&lits.iter().map(|l| dimacs(*l)).collect::<Vec<_>>());
+        }
+    }
+
+    fn log_delete(&mut self, lits: &[Lit]) {
+        if let Some(proof) = &mut self.proof {
+            proof.delete(&lits.iter().map(|l| dimacs(*l)).collect::<Vec<_>>());
+        }
+    }
+
+    fn decision_level(&self) -> u32 {
+        self.trail_lim.len() as u32
+    }
+
+    fn enqueue(&mut self, l: Lit, reason: Option<Reason>) {
+        let v = var(l);
+
+        self.assigns[v] = if l & 1 == 1 { -1 } else { 1 };
+        self.level[v] = self.decision_level();
+        self.reason[v] = reason;
+        self.trail.push(l);
+    }
+
+    fn attach(&mut self, lits: Vec<Lit>, learnt: bool, lbd: u32) -> usize {
+        let index = self.clauses.len();
+
+        self.watches[lits[0] as usize].push(Watch {
+            clause: index,
+            blocker: lits[1],
+        });
+
+        self.watches[lits[1] as usize].push(Watch {
+            clause: index,
+            blocker: lits[0],
+        });
+
+        if learnt {
+            self.learnts += 1;
+        }
+
+        self.clauses.push(Clause {
+            lits,
+            learnt,
+            lbd,
+            activity: 0.0,
+            deleted: false,
+        });
+
+        index
+    }
+
+    fn backtrack(&mut self, level: u32) {
+        if self.decision_level() <= level {
+            return;
+        }
+
+        let start = self.trail_lim[level as usize];
+
+        for l in self.trail.drain(start..) {
+            let v = var(l);
+
+            self.assigns[v] = 0;
+            self.reason[v] = None;
+            self.polarity[v] = l & 1 == 1;
+            self.heap.insert(v, &self.activity);
+        }
+
+        self.trail_lim.truncate(level as usize);
+        self.qhead = self.trail.len();
+        self.xhead = self.trail.len();
+
+        while self
+            .explanations
+            .last()
+            .is_some_and(|(index, _)| *index >= start)
+        {
+            self.explanations.pop();
+        }
+    }
+
+    /// Unit propagation over the clauses and the XOR constraints. Returns the reason of a
+    /// conflict, whose literals are all false.
+    fn propagate(&mut self) -> Option<Reason> {
+        loop {
+            if let Some(conflict) = self.propagate_clauses() {
+                return Some(Reason::Clause(conflict));
+            }
+
+            if self.xhead == self.trail.len() {
+                return None;
+            }
+
+            let mut deductions = Vec::new();
+
+            while self.xhead < self.trail.len() && deductions.is_empty() {
+                let v = var(self.trail[self.xhead]) as i32 + 1;
+                let (assigns, level) = (&self.assigns, &self.level);
+
+                self.xhead += 1;
+                self.xors.assign(
+                    v,
+                    |v| match assigns[v as usize - 1] {
+                        0 => None,
+                        a => Some((a == 1, level[v as usize - 1])),
+                    },
+                    &mut deductions,
+                );
+            }
+
+            if let Some(conflict) = self.deduce(deductions) {
+                return Some(conflict);
+            }
+        }
+    }
+
+    /// Assign the literals implied by the XOR constraints. Returns the reason of a conflict,
+    /// after backtracking to the highest level of its literals so that conflict analysis finds
+    /// one at the current level.
+    fn deduce(&mut self, deductions: Vec<Deduction>) -> Option<Reason> {
+        for deduction in deductions {
+            let lits: Vec<Lit> = match &deduction {
+                Deduction::Conflict(clause) | Deduction::Implied(clause) => {
+                    clause.iter().map(|l| lit(*l)).collect()
+                }
+            };
+
+            match value(&self.assigns, lits[0]) {
+                1 => continue,
+
+                0 => {
+                    self.explanations.push((self.trail.len(), lits));
+
+                    let reason = Reason::Xor(self.explanations.len() - 1);
+                    let l = self.explanations.last().unwrap().1[0];
+
+                    self.enqueue(l, Some(reason));
+                }
+
+                _ => {
+                    let level = lits.iter().map(|l| self.level[var(*l)]).max().unwrap();
+
+                    self.backtrack(level);
+                    self.explanations.push((self.trail.len(), lits));
+                    self.qhead = self.trail.len();
+
+                    return Some(Reason::Xor(self.explanations.len() - 1));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The literals of a reason, the propagated literal first.
+    fn reason_lits(&self, reason: Reason) -> &[Lit] {
+        match reason {
+            Reason::Clause(c) => &self.clauses[c].lits,
+            Reason::Xor(x) => &self.explanations[x].1,
+        }
+    }
+
+    /// Unit propagation over the two-watched-literal scheme. Returns a conflicting clause.
+    fn propagate_clauses(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let p = self.trail[self.qhead];
+            self.qhead += 1;
+            self.stats.propagations += 1;
+
+            // literals are only propagated once at the root, where they are fixed for good
+            if self.trail_lim.is_empty() {
+                self.stats.units += 1;
+
+                if let Some(observer) = &mut self.observer {
+                    observer.unit(dimacs(p));
+                }
+            }
+
+            let false_lit = neg(p);
+            let mut watches = std::mem::take(&mut self.watches[false_lit as usize]);
+
+            let mut i = 0;
+            let mut j = 0;
+            let mut conflict = None;
+
+            while i < watches.len() {
+                let watch = watches[i];
+                i += 1;
+
+                if value(&self.assigns, watch.blocker) == 1 {
+                    watches[j] = watch;
+                    j += 1;
+                    continue;
+                }
+
+                let clause = &mut self.clauses[watch.clause];
+
+                if clause.deleted {
+                    continue;
+                }
+
+                if clause.lits[0] == false_lit {
+                    clause.lits.swap(0, 1);
+                }
+
+                let first = clause.lits[0];
+                let kept = Watch {
+                    clause: watch.clause,
+                    blocker: first,
+                };
+
+                if first != watch.blocker && value(&self.assigns, first) == 1 {
+                    watches[j] = kept;
+                    j += 1;
+                    continue;
+                }
+
+                let assigns = &self.assigns;
+                let replacement =
+                    (2..clause.lits.len()).find(|k| value(assigns, clause.lits[*k]) != -1);
+
+                if let Some(k) = replacement {
+                    clause.lits.swap(1, k);
+
+                    let other = clause.lits[1];
+                    self.watches[other as usize].push(kept);
+                    continue;
+                }
+
+                watches[j] = kept;
+                j += 1;
+
+                if value(&self.assigns, first) == -1 {
+                    conflict = Some(watch.clause);
+
+                    while i < watches.len() {
+                        watches[j] = watches[i];
+                        i += 1;
+                        j += 1;
+                    }
+                } else {
+                    self.enqueue(first, Some(Reason::Clause(watch.clause)));
+                }
+            }
+
+            watches.truncate(j);
+            self.watches[false_lit as usize] = watches;
+
+            if conflict.is_some() {
+                self.qhead = self.trail.len();
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    fn bump_var(&mut self, v: usize) {
+        self.activity[v] += self.var_inc;
+
+        if self.activity[v] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+
+            self.var_inc *= 1e-100;
+        }
+
+        self.heap.update(v, &self.activity);
+    }
+
+    fn bump_clause(&mut self, c: usize) {
+        self.clauses[c].activity += self.clause_inc;
+
+        if self.clauses[c].activity > 1e20 {
+            for clause in self.clauses.iter_mut().filter(|c| c.learnt) {
+                clause.activity *= 1e-20;
+            }
+
+            self.clause_inc *= 1e-20;
+        }
+    }
+
+    /// First-UIP conflict analysis. Returns the learnt clause, asserting literal first, and the
+    /// level to backtrack to.
+    fn analyze(&mut self, mut conflict: Reason) -> (Vec<Lit>, u32) {
+        let mut learnt = vec![0];
+        let mut path = 0;
+        let mut index = self.trail.len();
+        let mut p: Option<Lit> = None;
+
+        loop {
+            if let Reason::Clause(c) = conflict {
+                if self.clauses[c].learnt {
+                    self.bump_clause(c);
+                }
+            }
+
+            let start = if p.is_some() { 1 } else { 0 };
+
+            for k in start..self.reason_lits(conflict).len() {
+                let q = self.reason_lits(conflict)[k];
+                let v = var(q);
+
+                if !self.seen[v] && self.level[v] > 0 {
+                    self.seen[v] = true;
+                    self.bump_var(v);
+
+                    if self.level[v] >= self.decision_level() {
+                        path += 1;
+                    } else {
+                        learnt.push(q);
+                    }
+                }
+            }
+
+            loop {
+                index -= 1;
+
+                if self.seen[var(self.trail[index])] {
+                    break;
+                }
+            }
+
+            let l = self.trail[index];
+            p = Some(l);
+            self.seen[var(l)] = false;
+            path -= 1;
+
+            if path == 0 {
+                break;
+            }
+
+            conflict = self.reason[var(l)].unwrap();
+        }
+
+        learnt[0] = neg(p.unwrap());
+
+        // drop literals implied by other literals of the learnt clause
+        let mut minimized = vec![learnt[0]];
+
+        for q in &learnt[1..] {
+            let redundant = match self.reason[var(*q)] {
+                None => false,
+                Some(r) => self.reason_lits(r)[1..]
+                    .iter()
+                    .all(|l| self.seen[var(*l)] || self.level[var(*l)] == 0),
+            };
+
+            if !redundant {
+                minimized.push(*q);
+            }
+        }
+
+        for q in &learnt {
+            self.seen[var(*q)] = false;
+        }
+
+        let mut learnt = minimized;
+
+        let level = if learnt.len() == 1 {
+            0
+        } else {
+            let max = (1..learnt.len())
+                .max_by_key(|k| self.level[var(learnt[*k])])
+                .unwrap();
+
+            learnt.swap(1, max);
+            self.level[var(learnt[1])]
+        };
+
+        (learnt, level)
+    }
+
+    /// Collect the assumptions implying the complement of the assumption `p`, which was found to
+    /// be false, into `failed`.
+    fn analyze_final(&mut self, p: Lit) {
+        self.failed.push(dimacs(p));
+
+        if self.decision_level() == 0 {
+            return;
+        }
+
+        self.seen[var(p)] = true;
+
+        for index in (self.trail_lim[0]..self.trail.len()).rev() {
+            let l = self.trail[index];
+            let v = var(l);
+
+            if !self.seen[v] {
+                continue;
+            }
+
+            match self.reason[v] {
+                None => self.failed.push(dimacs(l)),
+
+                Some(r) => {
+                    for k in 1..self.reason_lits(r).len() {
+                        let q = self.reason_lits(r)[k];
+
+                        if self.level[var(q)] > 0 {
+                            self.seen[var(q)] = true;
+                        }
+                    }
+                }
+            }
+
+            self.seen[v] = false;
+        }
+
+        self.seen[var(p)] = false;
+    }
+
+    fn lbd(&mut self, lits: &[Lit]) -> u32 {
+        let mut levels: Vec<u32> = lits.iter().map(|l| self.level[var(*l)]).collect();
+
+        levels.sort_unstable();
+        levels.dedup();
+
+        levels.len() as u32
+    }
+
+    fn pick_branch(&mut self) -> Option<Lit> {
+        while let Some(v) = self.heap.pop(&self.activity) {
+            if self.assigns[v] == 0 {
+                return Some(((v as u32) << 1) | self.polarity[v] as u32);
+            }
+        }
+
+        None
+    }
+
+    /// Add the clauses of the import callback at the root, returning false if they make the
+    /// clauses unsatisfiable.
+    fn import(&mut self) -> bool {
+        let clauses = match &mut self.import {
+            Some(import) => import(),
+            None => return true,
+        };
+
+        for clause in clauses {
+            let mut lits: Vec<Lit> = clause.iter().map(|l| lit(*l)).collect();
+
+            if let Some(max) = lits.iter().map(|l| var(*l)).max() {
+                self.reserve(max + 1);
+            }
+
+            lits.sort_unstable();
+            lits.dedup();
+
+            if lits.iter().any(|l| value(&self.assigns, *l) == 1) {
+                continue;
+            }
+
+            lits.retain(|l| value(&self.assigns, *l) == 0);
+
+            match lits.len() {
+                0 => self.ok = false,
+
+                1 => {
+                    self.enqueue(lits[0], None);
+
+                    if self.propagate().is_some() {
+                        self.ok = false;
+                    }
+                }
+
+                n => {
+                    self.attach(lits, true, n as u32);
+                }
+            }
+
+            if !self.ok {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    fn timed_out(&self) -> bool {
+        matches!(self.deadline, Some(deadline) if Instant::now() >= deadline)
+    }
+
+    fn terminated(&mut self) -> bool {
+        if let Some(terminator) = &self.terminator {
+            let conflicts = self.stats.conflicts - self.started;
+
+            if terminator.should_stop(conflicts)
+                || (conflicts.is_multiple_of(1024) && terminator.out_of_memory())
+            {
+                return true;
+            }
+        }
+
+        match &mut self.terminate {
+            Some(terminate) => terminate(),
+            None => false,
+        }
+    }
+
+    /// Search until a conclusion is reached or `budget` conflicts have occurred, in which case
+    /// `None` is returned after backtracking to the root.
+    fn search(&mut self, budget: u64) -> Option<Status> {
+        let mut conflicts = 0;
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.stats.conflicts += 1;
+                conflicts += 1;
+
+                if self.decision_level() == 0 {
+                    self.ok = false;
+                    self.log_add(&[]);
+                    return Some(Status::Unsat);
+                }
+
+                let (learnt, level) = self.analyze(conflict);
+                let lbd = self.lbd(&learnt);
+
+                self.log_add(&learnt);
+                self.stats.learnts += 1;
+
+                if let Some(observer) = &mut self.observer {
+                    observer.learnt(&learnt.iter().map(|l| dimacs(*l)).collect::<Vec<_>>());
+                }
+
+                if let Some((max_length, learn)) = &mut self.learn {
+                    if learnt.len() <= *max_length {
+                        learn(&learnt.iter().map(|l| dimacs(*l)).collect::<Vec<_>>());
+                    }
+                }
+
+                self.backtrack(level);
+
+                if learnt.len() == 1 {
+                    self.enqueue(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let index = self.attach(learnt, true, lbd);
+
+                    self.bump_clause(index);
+                    self.enqueue(asserting, Some(Reason::Clause(index)));
+                }
+
+                self.var_inc /= 0.95;
+                self.clause_inc /= 0.999;
+
+                if (self.stats.conflicts.is_multiple_of(64) && self.timed_out())
+                    || self.terminated()
+                {
+                    self.backtrack(0);
+                    return Some(Status::Unknown);
+                }
+            } else {
+                if conflicts >= budget {
+                    self.backtrack(0);
+                    return None;
+                }
+
+                // restarts may grow far apart, so learnt clauses are reduced and imported on
+                // schedules of their own
+                if self.learnts as f64 >= self.max_learnts {
+                    self.reduce();
+                    self.max_learnts *= 1.1;
+                }
+
+                if self.import.is_some() && self.stats.conflicts >= self.next_import {
+                    self.next_import = self.stats.conflicts + IMPORT_INTERVAL;
+                    self.backtrack(0);
+
+                    if !self.import() {
+                        return Some(Status::Unsat);
+                    }
+
+                    continue;
+                }
+
+                let mut next = None;
+
+                // each assumption gets a decision level of its own, empty if it already holds
+                while (self.decision_level() as usize) < self.assumptions.len() {
+                    let p = self.assumptions[self.decision_level() as usize];
+
+                    match value(&self.assigns, p) {
+                        1 => self.trail_lim.push(self.trail.len()),
+
+                        -1 => {
+                            self.analyze_final(p);
+                            return Some(Status::Unsat);
+                        }
+
+                        _ => {
+                            next = Some(p);
+                            break;
+                        }
+                    }
+                }
+
+                let next = match next {
+                    Some(l) => l,
+
+                    None => match self.pick_branch() {
+                        Some(l) => {
+                            self.stats.decisions += 1;
+
+                            if let Some(observer) = &mut self.observer {
+                                observer.decision(dimacs(l));
+                            }
+
+                            l
+                        }
+
+                        None => {
+                            self.model = self.assigns.iter().map(|v| *v == 1).collect();
+                            return Some(Status::Sat);
+                        }
+                    },
+                };
+
+                self.trail_lim.push(self.trail.len());
+                self.enqueue(next, None);
+            }
+        }
+    }
+
+    /// Delete the less useful half of the learnt clauses, keeping the reasons of the current
+    /// assignments, and compact the clause database.
+    fn reduce(&mut self) {
+        let locked = self.locked();
+        let mut candidates: Vec<usize> = (0..self.clauses.len())
+            .filter(|c| self.clauses[*c].learnt && !locked[*c] && self.clauses[*c].lbd > 2)
+            .collect();
+
+        candidates.sort_by(|a, b| {
+            let (a, b) = (&self.clauses[*a], &self.clauses[*b]);
+
+            b.lbd
+                .cmp(&a.lbd)
+                .then(a.activity.partial_cmp(&b.activity).unwrap())
+        });
+
+        for c in candidates.iter().take(candidates.len() / 2) {
+            let lits = std::mem::take(&mut self.clauses[*c].lits);
+
+            self.log_delete(&lits);
+            self.clauses[*c].deleted = true;
+            self.learnts -= 1;
+        }
+
+        self.compact();
+    }
+
+    /// Which clauses are the reason of an assignment.
+    fn locked(&self) -> Vec<bool> {
+        let mut locked = vec![false; self.clauses.len()];
+
+        for l in &self.trail {
+            if let Some(Reason::Clause(r)) = self.reason[var(*l)] {
+                locked[r] = true;
+            }
+        }
+
+        locked
+    }
+
+    /// Drop the deleted clauses from the clause database and watch the others anew.
+    fn compact(&mut self) {
+        let mut remap = vec![usize::MAX; self.clauses.len()];
+        let mut kept = Vec::with_capacity(self.clauses.len());
+
+        for (c, clause) in std::mem::take(&mut self.clauses).into_iter().enumerate() {
+            if !clause.deleted {
+                remap[c] = kept.len();
+                kept.push(clause);
+            }
+        }
+
+        self.clauses = kept;
+
+        for r in self.reason.iter_mut().flatten() {
+            if let Reason::Clause(c) = r {
+                *c = remap[*c];
+            }
+        }
+
+        for watches in self.watches.iter_mut() {
+            watches.clear();
+        }
+
+        for (c, clause) in self.clauses.iter().enumerate() {
+            self.watches[clause.lits[0] as usize].push(Watch {
+                clause: c,
+                blocker: clause.lits[1],
+            });
+
+            self.watches[clause.lits[1] as usize].push(Watch {
+                clause: c,
+                blocker: clause.lits[0],
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::{pigeonhole, random_3sat, random_clause};
+
+    fn satisfies(clauses: &[Vec<i32>], model: &[i32]) -> bool {
+        clauses
+            .iter()
+            .all(|clause| clause.iter().any(|l| model.contains(l)))
+    }
+
+    fn brute_force(num_vars: usize, clauses: &[Vec<i32>]) -> Status {
+        for bits in 0u64..1 << num_vars {
+            let model: Vec<i32> = (1..=num_vars as i32)
+                .map(|v| if bits >> (v - 1) & 1 == 1 { v } else { -v })
+                .collect();
+
+            if satisfies(clauses, &model) {
+                return Status::Sat;
+            }
+        }
+
+        Status::Unsat
+    }
+
+    #[test]
+    fn sat_01() {
+        let clauses = vec![
+            vec![1, -5, 4],
+            vec![-1, 5, 3, 4],
+            vec![-3, -4],
+            vec![2, 3],
+            vec![-2, -3, 5],
+        ];
+
+        let mut solver = Solver::new();
+
+        for clause in &clauses {
+            solver.add_clause(clause);
+        }
+
+        assert_eq!(solver.solve(), Status::Sat);
+        assert!(satisfies(&clauses, &solver.model()));
+    }
+
+    #[test]
+    fn unsat_01() {
+        let mut solver = Solver::new();
+
+        for clause in pigeonhole(6) {
+            solver.add_clause(&clause);
+        }
+
+        assert_eq!(solver.solve(), Status::Unsat);
+    }
+
+    #[test]
+    fn proof_01() {
+        let mut solver = Solver::new();
+
+        solver.set_proof(Drat::new(std::io::sink(), false));
+
+        for clause in pigeonhole(4) {
+            solver.add_clause(&clause);
+        }
+
+        assert_eq!(solver.solve(), Status::Unsat);
+        assert!(solver.take_proof().unwrap().finish().is_ok());
+    }
+
+    #[test]
+    fn assumptions_01() {
+        let mut solver = Solver::new();
+
+        for clause in &[vec![1, 2], vec![-1, 3], vec![-2, 3], vec![-4, -5]] {
+            solver.add_clause(clause);
+        }
+
+        assert_eq!(solver.solve_with_assumptions(&[1, 6]), Status::Sat);
+        assert_eq!(solver.value(3), Some(true));
+        assert_eq!(solver.value(6), Some(true));
+
+        assert_eq!(solver.solve_with_assumptions(&[6, -3, 4]), Status::Unsat);
+        assert_eq!(solver.failed(), &[-3]);
+
+        assert_eq!(solver.solve_with_assumptions(&[4, 6, 5]), Status::Unsat);
+
+        let mut failed = solver.failed().to_vec();
+        failed.sort_unstable();
+
+        assert_eq!(failed, vec![4, 5]);
+
+        assert_eq!(solver.solve(), Status::Sat);
+    }
+
+    #[test]
+    fn random_01() {
+        let mut rng = Rng::new(1);
+
+        for _ in 0..50 {
+            let clauses: Vec<Vec<i32>> = random_3sat(&mut rng, 12, 52);
+
+            let mut solver = Solver::new();
+
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+
+            let status = solver.solve();
+
+            assert_eq!(status, brute_force(12, &clauses));
+
+            if status == Status::Sat {
+                assert!(satisfies(&clauses, &solver.model()));
+            }
+        }
+    }
+
+    #[test]
+    fn xor_01() {
+        let mut rng = Rng::new(2);
+
+        for round in 0..50 {
+            let mut solver = Solver::new();
+            let mut clauses = vec![];
+
+            for _ in 0..36 {
+                let clause: Vec<i32> = random_clause(&mut rng, 12, 3);
+
+                solver.add_clause(&clause);
+                clauses.push(clause);
+            }
+
+            for _ in 0..2 + round % 6 {
+                let mut vars: Vec<i32> = (1..=12).collect();
+
+                for i in 0..4 {
+                    vars.swap(i, i + rng.below(12 - i as u64) as usize);
+                }
+
+                let xor: Vec<i32> = vars[..4]
+                    .iter()
+                    .map(|v| if rng.next_bool() { *v } else { -v })
+                    .collect();
+
+                solver.add_xor(&xor);
+
+                // the direct encoding forbids every assignment of even parity
+                for mask in 0..16u32 {
+                    if mask.count_ones() % 2 == 0 {
+                        clauses.push(
+                            (0..4)
+                                .map(|i| if mask >> i & 1 == 1 { -xor[i] } else { xor[i] })
+                                .collect(),
+                        );
+                    }
+                }
+            }
+
+            let status = solver.solve();
+
+            assert_eq!(status, brute_force(12, &clauses));
+
+            if status == Status::Sat {
+                assert!(satisfies(&clauses, &solver.model()));
+            }
+        }
+    }
+
+    #[test]
+    fn scopes_01() {
+        let mut solver = Solver::new();
+
+        solver.add_clause(&[1, 2]);
+        solver.push(10);
+        solver.add_clause(&[-1]);
+        solver.push(11);
+        solver.add_clause(&[-2]);
+
+        assert_eq!(solver.solve(), Status::Unsat);
+        assert_eq!(solver.failed(), &[] as &[i32]);
+
+        solver.pop();
+
+        assert_eq!(solver.solve_with_assumptions(&[3]), Status::Sat);
+        assert_eq!(solver.value(2), Some(true));
+
+        assert_eq!(solver.solve_with_assumptions(&[3, -2]), Status::Unsat);
+        assert_eq!(solver.failed(), &[-2]);
+
+        solver.pop();
+        solver.add_clause(&[-2]);
+
+        assert_eq!(solver.solve(), Status::Sat);
+        assert_eq!(solver.value(1), Some(true));
+    }
+
+    #[test]
+    fn scopes_02() {
+        let mut solver = Solver::new();
+
+        solver.add_clause(&[1, 2, 3]);
+
+        // the clauses of popped scopes do not pile up
+        for round in 0..20 {
+            solver.push(10 + round);
+            solver.add_clause(&[-1, -2]);
+            solver.add_clause(&[-2, -3]);
+            solver.add_clause(&[-1, -3]);
+
+            assert_eq!(
+                solver.solve_with_assumptions(&[-(round % 3 + 1)]),
+                Status::Sat
+            );
+
+            solver.pop();
+
+            assert_eq!(solver.clauses.len(), 1);
+        }
+
+        assert_eq!(solver.solve_with_assumptions(&[1, 2, 3]), Status::Sat);
+    }
+
+    #[test]
+    fn terminator_01() {
+        let mut solver = Solver::new();
+        let mut terminator = Terminator::new();
+
+        for clause in pigeonhole(8) {
+            solver.add_clause(&clause);
+        }
+
+        terminator.set_max_conflicts(100);
+        solver.set_terminator(terminator);
+
+        // the limit applies to each call
+        assert_eq!(solver.solve(), Status::Unknown);
+        assert_eq!(solver.stats().conflicts, 100);
+        assert_eq!(solver.solve(), Status::Unknown);
+        assert_eq!(solver.stats().conflicts, 200);
+
+        let terminator = Terminator::new();
+
+        solver.set_terminator(terminator.clone());
+        terminator.terminate();
+
+        // stopped before the first conflict
+        assert_eq!(solver.solve(), Status::Unknown);
+        assert_eq!(solver.stats().conflicts, 200);
+    }
+}
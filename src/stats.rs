@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+/// Callbacks on the events of a `Solver` or of variable elimination by `DavisPutnam`, such as to
+/// report progress. Every callback does nothing by default. Observers are sent along with
+/// their solver, such as to the threads of a portfolio.
+pub trait Observer: Send {
+    /// A clause was learnt from a conflict.
+    fn learnt(&mut self, clause: &[i32]) {}
+
+    /// The solver restarted, backtracking to the root.
+    fn restart(&mut self) {}
+
+    /// The solver decided the literal, not counting assumptions.
+    fn decision(&mut self, literal: i32) {}
+
+    /// A variable was eliminated by resolution on `pivot`, taking the clause set from `before`
+    /// to `after` clauses.
+    fn eliminated(&mut self, pivot: i32, before: usize, after: usize) {}
+
+    /// The literal was fixed at the root, for good.
+    fn unit(&mut self, literal: i32) {}
+}
+
+/// Counts of the events of a solver or of variable elimination, and the time spent in each
+/// phase. The statistics of both can be added up with `merge`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub conflicts: u64,
+    pub propagations: u64,
+    pub decisions: u64,
+    pub restarts: u64,
+    pub learnts: u64,
+    pub units: u64,
+    pub eliminations: u64,
+
+    /// Time spent searching in `Solver::solve_with_assumptions`.
+    pub search_time: Duration,
+
+    /// Time spent eliminating variables.
+    pub elimination_time: Duration,
+}
+
+impl Stats {
+    /// Add the counts and times of `other`.
+    pub fn merge(&mut self, other: &Stats) {
+        self.conflicts += other.conflicts;
+        self.propagations += other.propagations;
+        self.decisions += other.decisions;
+        self.restarts += other.restarts;
+        self.learnts += other.learnts;
+        self.units += other.units;
+        self.eliminations += other.eliminations;
+        self.search_time += other.search_time;
+        self.elimination_time += other.elimination_time;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dp::DavisPutnam;
+    use crate::solver::Solver;
+    use crate::testing::pigeonhole;
+    use crate::Status;
+    use std::sync::{Arc, Mutex};
+
+    /// Counts the events it is called back on.
+    struct Counts(Arc<Mutex<Stats>>);
+
+    impl Observer for Counts {
+        fn learnt(&mut self, clause: &[i32]) {
+            self.0.lock().unwrap().learnts += 1;
+        }
+
+        fn restart(&mut self) {
+            self.0.lock().unwrap().restarts += 1;
+        }
+
+        fn decision(&mut self, literal: i32) {
+            self.0.lock().unwrap().decisions += 1;
+        }
+
+        fn eliminated(&mut self, pivot: i32, before: usize, after: usize) {
+            self.0.lock().unwrap().eliminations += 1;
+        }
+
+        fn unit(&mut self, literal: i32) {
+            self.0.lock().unwrap().units += 1;
+        }
+    }
+
+    #[test]
+    fn observer_01() {
+        let counts = Arc::new(Mutex::new(Stats::default()));
+        let mut solver = Solver::new();
+
+        solver.set_observer(Some(Box::new(Counts(counts.clone()))));
+
+        for clause in pigeonhole(6) {
+            solver.add_clause(&clause);
+        }
+
+        assert_eq!(solver.solve(), Status::Unsat);
+
+        let stats = solver.stats();
+        let counts = counts.lock().unwrap();
+
+        assert!(stats.conflicts > 0 && stats.propagations > 0);
+        assert_eq!(counts.learnts, stats.learnts);
+        assert_eq!(counts.restarts, stats.restarts);
+        assert_eq!(counts.decisions, stats.decisions);
+        assert_eq!(counts.units, stats.units);
+    }
+
+    #[test]
+    fn observer_02() {
+        let counts = Arc::new(Mutex::new(Stats::default()));
+        let mut dp = DavisPutnam::new(pigeonhole(3));
+
+        dp.set_observer(Some(Box::new(Counts(counts.clone()))));
+
+        assert_eq!(dp.solve(), Status::Unsat);
+        assert!(dp.stats().eliminations > 0);
+        assert_eq!(counts.lock().unwrap().eliminations, dp.stats().eliminations);
+
+        let mut total = Stats::default();
+
+        total.merge(dp.stats());
+        total.merge(dp.stats());
+
+        assert_eq!(total.eliminations, 2 * dp.stats().eliminations);
+    }
+}
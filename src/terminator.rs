@@ -0,0 +1,111 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Cooperative cancellation of long-running operations, such as solving, counting and variable
+/// elimination. An operation polls its terminator as it goes, and gives up reporting
+/// `Status::Unknown` once the terminator is stopped by another thread through `terminate`, or
+/// once any of its limits is exceeded.
+///
+/// Clones share the flag set by `terminate`, so that one call stops every operation given a
+/// clone, while the limits are copied.
+#[derive(Clone, Debug, Default)]
+pub struct Terminator {
+    flag: Arc<AtomicBool>,
+    deadline: Option<Instant>,
+    max_conflicts: Option<u64>,
+    max_memory: Option<usize>,
+}
+
+impl Terminator {
+    /// Create a terminator without any limit.
+    pub fn new() -> Terminator {
+        Terminator::default()
+    }
+
+    /// Stop the operations polling this terminator or a clone of it.
+    pub fn terminate(&self) {
+        self.flag.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether `terminate` has been called.
+    pub fn is_terminated(&self) -> bool {
+        self.flag.load(Ordering::Relaxed)
+    }
+
+    /// Stop once the deadline has passed.
+    pub fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    /// Stop once an operation has run into `max_conflicts` conflicts, counted by each
+    /// operation from its start.
+    pub fn set_max_conflicts(&mut self, max_conflicts: u64) {
+        self.max_conflicts = Some(max_conflicts);
+    }
+
+    /// Stop once the resident memory of the process exceeds `max_memory` bytes. Only
+    /// supported where `/proc/self/status` gives it, such as on Linux.
+    pub fn set_max_memory(&mut self, max_memory: usize) {
+        self.max_memory = Some(max_memory);
+    }
+
+    /// Whether an operation having run into `conflicts` conflicts should stop, not counting the
+    /// memory limit. Cheap enough to poll at every conflict.
+    pub fn should_stop(&self, conflicts: u64) -> bool {
+        self.is_terminated()
+            || matches!(self.max_conflicts, Some(max) if conflicts >= max)
+            || matches!(self.deadline, Some(deadline) if Instant::now() >= deadline)
+    }
+
+    /// Whether the memory limit is exceeded. Reads the memory in use from the system, so meant
+    /// to be polled less often than `should_stop`.
+    pub fn out_of_memory(&self) -> bool {
+        match self.max_memory {
+            Some(max) => resident_memory().is_some_and(|memory| memory > max),
+            None => false,
+        }
+    }
+}
+
+/// The resident memory of the process in bytes, if known.
+fn resident_memory() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    let kb: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn simple_01() {
+        let mut terminator = Terminator::new();
+
+        assert!(!terminator.should_stop(1000));
+
+        terminator.set_max_conflicts(100);
+
+        assert!(!terminator.should_stop(99));
+        assert!(terminator.should_stop(100));
+
+        let clone = terminator.clone();
+
+        clone.terminate();
+
+        assert!(terminator.is_terminated());
+        assert!(terminator.should_stop(0));
+        assert!(!terminator.out_of_memory());
+
+        // any process takes more than a byte
+        let mut terminator = Terminator::new();
+
+        terminator.set_max_memory(1);
+
+        assert!(terminator.out_of_memory() || fs::metadata("/proc/self/status").is_err());
+    }
+}
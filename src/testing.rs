@@ -0,0 +1,44 @@
+//! Instances shared by the tests of several modules.
+
+use crate::rng::Rng;
+
+/// Pigeonhole principle: `n + 1` pigeons do not fit into `n` holes.
+pub(crate) fn pigeonhole(n: i32) -> Vec<Vec<i32>> {
+    let p = |i: i32, j: i32| i * n + j + 1;
+    let mut clauses = vec![];
+
+    for i in 0..=n {
+        clauses.push((0..n).map(|j| p(i, j)).collect());
+    }
+
+    for j in 0..n {
+        for i in 0..=n {
+            for k in i + 1..=n {
+                clauses.push(vec![-p(i, j), -p(k, j)]);
+            }
+        }
+    }
+
+    clauses
+}
+
+/// A clause of `len` random literals over the variables `1..=num_vars`, which may repeat.
+pub(crate) fn random_clause(rng: &mut Rng, num_vars: u64, len: usize) -> Vec<i32> {
+    (0..len)
+        .map(|_| {
+            let v = rng.below(num_vars) as i32 + 1;
+            if rng.next_bool() {
+                v
+            } else {
+                -v
+            }
+        })
+        .collect()
+}
+
+/// `num_clauses` random clauses of three literals over the variables `1..=num_vars`.
+pub(crate) fn random_3sat(rng: &mut Rng, num_vars: u64, num_clauses: usize) -> Vec<Vec<i32>> {
+    (0..num_clauses)
+        .map(|_| random_clause(rng, num_vars, 3))
+        .collect()
+}
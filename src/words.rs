@@ -54,6 +54,16 @@ impl Word {
         self.ids.len()
     }
 
+    /// The expression ids of the bits of the word, least significant first.
+    pub fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    /// The container holding the expressions of the word.
+    pub fn bits(&self) -> &Bits {
+        &self.bits
+    }
+
     pub fn from_u64(bits: &Bits, width: usize, val: u64) -> Word {
         let mut ids = Vec::with_capacity(width);
 
@@ -167,7 +177,7 @@ impl Word {
     }
 
     pub fn all(&self) -> Word {
-        let init = self.bits.val(false);
+        let init = self.bits.val(true);
         let word = self.fold(init, |bits, a, b| bits.and(a, b));
 
         self.bits.decr(init);
@@ -176,7 +186,7 @@ impl Word {
     }
 
     pub fn any(&self) -> Word {
-        let init = self.bits.val(true);
+        let init = self.bits.val(false);
         let word = self.fold(init, |bits, a, b| bits.or(a, b));
 
         self.bits.decr(init);
@@ -193,9 +203,37 @@ impl Word {
         word
     }
 
+    /// Unsigned comparison, as a word of width one.
     pub fn less_than(&self, rhs: &Word) -> Word {
-        todo!()
-        //self.ids.iter().rev().zip(rhs.ids.iter().rev()).
+        assert!(self.bits.ptr_eq(&rhs.bits));
+        assert_eq!(self.width(), rhs.width());
+
+        let bits = &self.bits;
+        let mut lt = bits.val(false);
+
+        // a more significant bit decides the comparison unless both bits are equal
+        for (a, b) in self.ids.iter().zip(rhs.ids.iter()) {
+            let t1 = bits.not(*a);
+            let t2 = bits.and(t1, *b);
+            let t3 = bits.xor(*a, *b);
+            let t4 = bits.not(t3);
+            let t5 = bits.and(t4, lt);
+            let c = bits.or(t2, t5);
+
+            bits.decr(t1);
+            bits.decr(t2);
+            bits.decr(t3);
+            bits.decr(t4);
+            bits.decr(t5);
+            bits.decr(lt);
+
+            lt = c;
+        }
+
+        Word {
+            bits: bits.clone(),
+            ids: vec![lt],
+        }
     }
 }
 
@@ -301,10 +339,7 @@ impl Shr<usize> for &Word {
                 .iter()
                 .skip(rhs)
                 .copied()
-                .map(|a| {
-                    self.bits.incr(a);
-                    a
-                })
+                .inspect(|&a| self.bits.incr(a))
                 .chain(std::iter::repeat_with(|| self.bits.val(false)))
                 .take(self.width())
                 .collect(),
@@ -320,10 +355,7 @@ impl Shl<usize> for &Word {
             bits: self.bits.clone(),
             ids: std::iter::repeat_with(|| self.bits.val(false))
                 .take(rhs)
-                .chain(self.ids.iter().copied().map(|a| {
-                    self.bits.incr(a);
-                    a
-                }))
+                .chain(self.ids.iter().copied().inspect(|&a| self.bits.incr(a)))
                 .take(self.width())
                 .collect(),
         }
@@ -442,6 +474,7 @@ pub enum Bit {
     And(u32, u32),
     Or(u32, u32),
     Not(u32),
+    Xor(u32, u32),
 }
 
 struct _Bits {
@@ -544,19 +577,31 @@ impl Bits {
         c
     }
 
+    /// Create the exclusive or of two expressions
     pub fn xor(&self, a: u32, b: u32) -> u32 {
-        let t1 = self.not(a);
-        let t2 = self.not(b);
+        if self.is_false(a) {
+            self.incr(b);
+            return b;
+        }
+
+        if self.is_false(b) {
+            self.incr(a);
+            return a;
+        }
 
-        let t3 = self.and(t1, b);
-        let t4 = self.and(a, t2);
+        if self.is_true(a) {
+            return self.not(b);
+        }
 
-        let c = self.or(t3, t4);
+        if self.is_true(b) {
+            return self.not(a);
+        }
 
-        self.decr(t1);
-        self.decr(t2);
-        self.decr(t3);
-        self.decr(t4);
+        let c = self.alloc_bit(Bit::Xor(a, b));
+
+        self.incr(a);
+        self.incr(b);
+        self.incr(c);
 
         c
     }
@@ -641,6 +686,11 @@ impl Bits {
                         pending.push(r);
                     }
 
+                    Bit::Xor(l, r) => {
+                        pending.push(l);
+                        pending.push(r);
+                    }
+
                     Bit::Not(e) => pending.push(e),
                 }
             }
@@ -691,6 +741,7 @@ impl Bits {
             Bit::And(l, r) => 1 + std::cmp::max(self.depth(l), self.depth(r)),
             Bit::Or(l, r) => 1 + std::cmp::max(self.depth(l), self.depth(r)),
             Bit::Not(e) => 1 + self.depth(e),
+            Bit::Xor(l, r) => 1 + std::cmp::max(self.depth(l), self.depth(r)),
         }
     }
 }
@@ -1119,4 +1170,40 @@ mod test {
 
         assert_eq!(total_refcounts(&bits), 0, "refcount expected to be zero");
     }
+
+    #[test]
+    fn less_than_01() {
+        let bits = Bits::new();
+
+        for k in 0..=MAX {
+            for j in 0..=MAX {
+                let a = Word::from_u64(&bits, BITS, k);
+                let b = Word::from_u64(&bits, BITS, j);
+                let c = a.less_than(&b);
+
+                assert_eq!(u64::try_from(&c).unwrap(), (k < j) as u64);
+            }
+        }
+
+        assert_eq!(total_refcounts(&bits), 0, "refcount expected to be zero");
+    }
+
+    #[test]
+    fn reduce_01() {
+        let bits = Bits::new();
+
+        for k in 0..=MAX {
+            let a = Word::from_u64(&bits, BITS, k);
+
+            let all = u64::try_from(&a.all()).unwrap();
+            let any = u64::try_from(&a.any()).unwrap();
+            let parity = u64::try_from(&a.parity()).unwrap();
+
+            assert_eq!(all, (k == MAX) as u64);
+            assert_eq!(any, (k != 0) as u64);
+            assert_eq!(parity, k.count_ones() as u64 % 2);
+        }
+
+        assert_eq!(total_refcounts(&bits), 0, "refcount expected to be zero");
+    }
 }
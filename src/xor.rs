@@ -0,0 +1,404 @@
+/// What the system of XOR constraints deduces from an assignment. Both come with a clause
+/// implied by the system whose other literals are false: a conflict with every literal false,
+/// and an implication with the implied literal first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Deduction {
+    Conflict(Vec<i32>),
+    Implied(Vec<i32>),
+}
+
+#[derive(Clone)]
+struct Row {
+    bits: Vec<u64>,
+    rhs: bool,
+}
+
+impl Row {
+    fn get(&self, c: usize) -> bool {
+        self.bits[c / 64] >> (c % 64) & 1 == 1
+    }
+
+    fn flip(&mut self, c: usize) {
+        self.bits[c / 64] ^= 1 << (c % 64);
+    }
+
+    fn add(&mut self, other: &Row) {
+        for (a, b) in self.bits.iter_mut().zip(other.bits.iter()) {
+            *a ^= b;
+        }
+
+        self.rhs ^= other.rhs;
+    }
+
+    fn is_zero(&self) -> bool {
+        self.bits.iter().all(|bits| *bits == 0)
+    }
+
+    fn columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(w, bits)| {
+            let mut bits = *bits;
+
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+
+                let b = bits.trailing_zeros() as usize;
+
+                bits &= bits - 1;
+                Some(64 * w + b)
+            })
+        })
+    }
+}
+
+/// A system of XOR constraints, kept as the rows of a matrix over GF(2) with one column per
+/// variable. A constraint is given in the CryptoMiniSat convention as literals whose exclusive
+/// or is true, so `1 -2` states that variables 1 and 2 are equal.
+///
+/// The matrix is kept in reduced row echelon form as constraints are added: each row has a
+/// basic column that no other row has. Under an assignment, each row watches its basic column
+/// and one other unassigned column, much like the two watched literals of a clause. When the
+/// basic variable of a row is assigned, an unassigned column of the row becomes basic instead,
+/// and is eliminated from the other rows. Once a row has a single unassigned variable it
+/// implies it, and once it has none it may be in conflict. Row operations keep the system
+/// equivalent whatever the assignment, so nothing needs to be undone on backtracking.
+#[derive(Clone, Default)]
+pub struct Gauss {
+    vars: Vec<i32>,
+    columns: Vec<Option<usize>>,
+    rows: Vec<Row>,
+
+    basic: Vec<usize>,
+    watch: Vec<Option<usize>>,
+    watches: Vec<Vec<usize>>,
+}
+
+impl Gauss {
+    /// Create an empty system.
+    pub fn new() -> Gauss {
+        Gauss::default()
+    }
+
+    /// The number of independent constraints in the system.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Add a constraint. Each negative literal flips the parity, and repeated variables cancel.
+    /// Returns false if the system has no solution anymore. Constraints implied by the system
+    /// are dropped.
+    pub fn add(&mut self, xor: &[i32]) -> bool {
+        let mut row = Row {
+            bits: Vec::new(),
+            rhs: true,
+        };
+
+        for literal in xor {
+            let c = self.column(literal.abs());
+
+            row.bits.resize(self.vars.len().div_ceil(64), 0);
+            row.flip(c);
+            row.rhs ^= *literal < 0;
+        }
+
+        let width = self.vars.len().div_ceil(64);
+
+        row.bits.resize(width, 0);
+
+        for r in self.rows.iter_mut() {
+            r.bits.resize(width, 0);
+        }
+
+        for (r, b) in self.rows.iter().zip(self.basic.iter()) {
+            if row.get(*b) {
+                row.add(r);
+            }
+        }
+
+        if row.is_zero() {
+            return !row.rhs;
+        }
+
+        let pivot = row.columns().next().unwrap();
+
+        for r in self.rows.iter_mut() {
+            if r.get(pivot) {
+                r.add(&row);
+            }
+        }
+
+        self.rows.push(row);
+        self.basic.push(pivot);
+        self.watch.push(None);
+
+        true
+    }
+
+    /// Watch every row anew under the assignment, given by `value` as the value and decision
+    /// level of each assigned variable, such as after adding constraints. Deductions are
+    /// appended to `out`.
+    pub fn rescan<F>(&mut self, value: F, out: &mut Vec<Deduction>)
+    where
+        F: Fn(i32) -> Option<(bool, u32)>,
+    {
+        self.watches = vec![Vec::new(); self.vars.len()];
+
+        for r in 0..self.rows.len() {
+            self.watches[self.basic[r]].push(r);
+            self.watch[r] = None;
+        }
+
+        for r in 0..self.rows.len() {
+            self.fix(r, &value, out);
+        }
+    }
+
+    /// Update the rows watching the variable `v`, which was just assigned. Deductions are
+    /// appended to `out`.
+    pub fn assign<F>(&mut self, v: i32, value: F, out: &mut Vec<Deduction>)
+    where
+        F: Fn(i32) -> Option<(bool, u32)>,
+    {
+        let c = match self.columns.get(v as usize) {
+            Some(Some(c)) => *c,
+            _ => return,
+        };
+
+        let mut rows = std::mem::take(&mut self.watches[c]);
+        let mut kept = Vec::with_capacity(rows.len());
+
+        // a row may be pushed again when its watch becomes basic
+        rows.sort_unstable();
+        rows.dedup();
+
+        for r in rows {
+            // rows whose watch has moved on are dropped lazily
+            if self.basic[r] != c && self.watch[r] != Some(c) {
+                continue;
+            }
+
+            self.fix(r, &value, out);
+
+            if self.basic[r] == c || self.watch[r] == Some(c) {
+                kept.push(r);
+            }
+        }
+
+        kept.append(&mut self.watches[c]);
+        self.watches[c] = kept;
+    }
+
+    fn column(&mut self, v: i32) -> usize {
+        let v = v as usize;
+
+        if self.columns.len() <= v {
+            self.columns.resize(v + 1, None);
+        }
+
+        match self.columns[v] {
+            Some(c) => c,
+
+            None => {
+                self.columns[v] = Some(self.vars.len());
+                self.vars.push(v as i32);
+                self.watches.push(Vec::new());
+                self.vars.len() - 1
+            }
+        }
+    }
+
+    fn set_watch(&mut self, r: usize, c: Option<usize>) {
+        if self.watch[r] != c {
+            self.watch[r] = c;
+
+            if let Some(c) = c {
+                self.watches[c].push(r);
+            }
+        }
+    }
+
+    /// Make column `c` of row `r` basic, eliminating it from the other rows, which are pushed
+    /// onto `changed`.
+    fn pivot(&mut self, r: usize, c: usize, changed: &mut Vec<usize>) {
+        let row = self.rows[r].clone();
+
+        for (s, other) in self.rows.iter_mut().enumerate() {
+            if s != r && other.get(c) {
+                other.add(&row);
+                changed.push(s);
+            }
+        }
+
+        self.basic[r] = c;
+        self.watches[c].push(r);
+    }
+
+    /// Restore the watches of row `r` and of the rows changed along the way, deducing what the
+    /// rows left with at most one unassigned variable imply.
+    fn fix<F>(&mut self, r: usize, value: &F, out: &mut Vec<Deduction>)
+    where
+        F: Fn(i32) -> Option<(bool, u32)>,
+    {
+        let mut changed = vec![r];
+
+        while let Some(r) = changed.pop() {
+            let unassigned = |c: usize| value(self.vars[c]).is_none();
+
+            let b = self.basic[r];
+            let watched = self.watch[r].filter(|w| *w != b && self.rows[r].get(*w));
+
+            // the usual case, with both watches still unassigned
+            if unassigned(b) && watched.is_some_and(unassigned) {
+                continue;
+            }
+
+            let free: Vec<usize> = self.rows[r]
+                .columns()
+                .filter(|c| unassigned(*c))
+                .take(2)
+                .collect();
+
+            if !unassigned(b) {
+                if let Some(c) = free.first() {
+                    self.pivot(r, *c, &mut changed);
+                }
+            }
+
+            let b = self.basic[r];
+
+            if let Some(w) = free.iter().find(|c| **c != b) {
+                self.set_watch(r, Some(*w));
+                continue;
+            }
+
+            // every other variable of the row is assigned, the last one watched
+            let mut parity = self.rows[r].rhs;
+            let mut clause = Vec::new();
+            let mut last = None;
+
+            for c in self.rows[r].columns().filter(|c| *c != b) {
+                let v = self.vars[c];
+                let (x, level) = value(v).unwrap();
+
+                parity ^= x;
+                clause.push(if x { -v } else { v });
+
+                if last.is_none_or(|(_, l)| level >= l) {
+                    last = Some((c, level));
+                }
+            }
+
+            self.set_watch(r, last.map(|(c, _)| c));
+
+            let v = self.vars[b];
+
+            match value(v) {
+                None => {
+                    clause.insert(0, if parity { v } else { -v });
+                    out.push(Deduction::Implied(clause));
+                }
+
+                Some((x, _)) if x != parity => {
+                    clause.insert(0, if x { -v } else { v });
+                    out.push(Deduction::Conflict(clause));
+                }
+
+                Some(_) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn deduce(gauss: &mut Gauss, assignment: &[i32]) -> Vec<Deduction> {
+        let value = |v: i32| {
+            assignment
+                .iter()
+                .position(|l| l.abs() == v)
+                .map(|i| (assignment[i] > 0, i as u32))
+        };
+
+        let mut out = Vec::new();
+
+        gauss.rescan(value, &mut out);
+        out
+    }
+
+    #[test]
+    fn simple_01() {
+        let mut gauss = Gauss::new();
+
+        // x1 + x2 + x3 = 1, x2 + x3 = 0
+        assert!(gauss.add(&[1, 2, 3]));
+        assert!(gauss.add(&[2, -3]));
+
+        assert_eq!(deduce(&mut gauss, &[]), vec![Deduction::Implied(vec![1])]);
+
+        assert_eq!(
+            deduce(&mut gauss, &[2]),
+            vec![Deduction::Implied(vec![1]), Deduction::Implied(vec![3, -2])]
+        );
+
+        // the second row has x3 as its basic variable since x2 was assigned
+        assert_eq!(
+            deduce(&mut gauss, &[2, -3]),
+            vec![
+                Deduction::Implied(vec![1]),
+                Deduction::Conflict(vec![3, -2])
+            ]
+        );
+
+        // implied by the others, or contradicting them
+        assert!(gauss.add(&[1, 2, 2]));
+        assert_eq!(gauss.len(), 2);
+        assert!(!gauss.add(&[-1]));
+    }
+
+    #[test]
+    fn assign_01() {
+        let mut gauss = Gauss::new();
+        let mut assignment: Vec<i32> = Vec::new();
+
+        // x1 + x2 + x3 + x4 = 1, x3 + x4 + x5 = 0
+        gauss.add(&[1, 2, 3, 4]);
+        gauss.add(&[3, 4, -5]);
+
+        assert!(deduce(&mut gauss, &assignment).is_empty());
+
+        let mut out = Vec::new();
+
+        for l in [3, -4, -1] {
+            assignment.push(l);
+
+            let value = |v: i32| {
+                assignment
+                    .iter()
+                    .position(|l| l.abs() == v)
+                    .map(|i| (assignment[i] > 0, i as u32))
+            };
+
+            gauss.assign(l.abs(), value, &mut out);
+        }
+
+        // x5 = x3 + x4 = 1 and then x2 = 1 + x1 + x3 + x4 = 0
+        let mut implied: Vec<i32> = out
+            .iter()
+            .map(|deduction| match deduction {
+                Deduction::Implied(clause) => clause[0],
+                Deduction::Conflict(_) => panic!("no conflict expected"),
+            })
+            .collect();
+
+        implied.sort_unstable();
+
+        assert_eq!(implied, vec![-2, 5]);
+    }
+}